@@ -0,0 +1,299 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{ops::Bound, sync::Arc};
+
+use futures::future::{self, Either, Future};
+use futures::prelude::TryFutureExt;
+use kvproto::metapb;
+
+use crate::{
+    raw::{prefixed_key, prefixed_range},
+    rpc::{Region, RpcClient},
+    Key, KeyRange, Result,
+};
+
+/// Read-only access to the metadata of the cluster: its stores, and how its keys are split into
+/// regions and placed on them.
+///
+/// The metadata is fetched from PD on every call, so it is always up to date. This is meant for
+/// inspecting the cluster, e.g. by operational tooling, rather than for every request.
+///
+/// The keys looked up are prefixed like those of the client the `ClusterInfo` was obtained from,
+/// see [`raw::Client::with_prefix`](crate::raw::Client::with_prefix). The boundaries of the
+/// regions returned are keys of the whole cluster, as a region may span several prefixes.
+///
+/// ```rust,no_run
+/// # #![feature(async_await)]
+/// # use tikv_client::{Config, raw::Client};
+/// # use futures::prelude::*;
+/// # futures::executor::block_on(async {
+/// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+/// # let connected_client = connecting_client.await.unwrap();
+/// let cluster = connected_client.cluster_info();
+/// for store in cluster.stores().await.unwrap() {
+///     println!("store {} at {} is {:?}", store.id(), store.address(), store.state());
+/// }
+/// for region in cluster.regions("TiDB".."TiKV").await.unwrap() {
+///     println!("region {} is led by {:?}", region.id(), region.leader());
+/// }
+/// # });
+/// ```
+#[derive(Clone)]
+pub struct ClusterInfo {
+    rpc: Arc<RpcClient>,
+    prefix: Key,
+}
+
+impl ClusterInfo {
+    pub(crate) fn new(rpc: Arc<RpcClient>, prefix: Key) -> Self {
+        ClusterInfo { rpc, prefix }
+    }
+
+    /// All stores of the cluster, including those which are offline or removed.
+    pub fn stores(&self) -> impl Future<Output = Result<Vec<StoreInfo>>> {
+        self.rpc
+            .stores()
+            .map_ok(|stores| stores.into_iter().map(StoreInfo::from).collect())
+    }
+
+    /// The region containing `key`.
+    pub fn region(&self, key: impl Into<Key>) -> impl Future<Output = Result<RegionInfo>> {
+        self.rpc
+            .region(&prefixed_key(&self.prefix, key.into()))
+            .map_ok(RegionInfo::from)
+    }
+
+    /// The regions overlapping `range`, ordered by their start keys. An unbounded range lists
+    /// all regions of the cluster.
+    pub fn regions(&self, range: impl KeyRange) -> impl Future<Output = Result<Vec<RegionInfo>>> {
+        let (start, end) = prefixed_range(&self.prefix, range);
+        let start = match start {
+            Bound::Unbounded => Bound::Included(Key::default()),
+            start => start,
+        };
+        match (start, end).into_keys() {
+            Ok(range) => Either::Left(
+                self.rpc
+                    .regions(range)
+                    .map_ok(|regions| regions.into_iter().map(RegionInfo::from).collect()),
+            ),
+            Err(e) => Either::Right(future::err(e)),
+        }
+    }
+}
+
+/// The state of a store.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StoreState {
+    /// The store is serving requests.
+    Up,
+    /// The store is being removed from the cluster, and its data is moved to other stores.
+    Offline,
+    /// The store was removed from the cluster.
+    Tombstone,
+}
+
+/// A TiKV store, see [`ClusterInfo::stores`](ClusterInfo::stores).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StoreInfo {
+    id: u64,
+    address: String,
+    state: StoreState,
+    labels: Vec<(String, String)>,
+}
+
+impl StoreInfo {
+    /// The id PD assigned to the store.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The address clients connect to.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Whether the store is serving, being removed, or removed.
+    pub fn state(&self) -> StoreState {
+        self.state
+    }
+
+    /// The labels of the store as key-value pairs, e.g. which zone or host it is in.
+    pub fn labels(&self) -> &[(String, String)] {
+        &self.labels
+    }
+}
+
+impl From<metapb::Store> for StoreInfo {
+    fn from(store: metapb::Store) -> StoreInfo {
+        StoreInfo {
+            id: store.get_id(),
+            address: store.get_address().to_owned(),
+            state: match store.get_state() {
+                metapb::StoreState::Up => StoreState::Up,
+                metapb::StoreState::Offline => StoreState::Offline,
+                metapb::StoreState::Tombstone => StoreState::Tombstone,
+            },
+            labels: store
+                .get_labels()
+                .iter()
+                .map(|label| (label.get_key().to_owned(), label.get_value().to_owned()))
+                .collect(),
+        }
+    }
+}
+
+/// A replica of a region, see [`RegionInfo`](RegionInfo).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PeerInfo {
+    id: u64,
+    store_id: u64,
+    is_learner: bool,
+}
+
+impl PeerInfo {
+    /// The id of the peer, unique across all regions.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The id of the store the peer is placed on.
+    pub fn store_id(&self) -> u64 {
+        self.store_id
+    }
+
+    /// Whether the peer is a learner, which receives the data of the region but does not vote.
+    pub fn is_learner(&self) -> bool {
+        self.is_learner
+    }
+}
+
+impl From<&metapb::Peer> for PeerInfo {
+    fn from(peer: &metapb::Peer) -> PeerInfo {
+        PeerInfo {
+            id: peer.get_id(),
+            store_id: peer.get_store_id(),
+            is_learner: peer.get_is_learner(),
+        }
+    }
+}
+
+/// A region, i.e. a range of keys which is replicated as a unit, see
+/// [`ClusterInfo::regions`](ClusterInfo::regions).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegionInfo {
+    id: u64,
+    start_key: Key,
+    end_key: Key,
+    conf_ver: u64,
+    version: u64,
+    leader: Option<PeerInfo>,
+    peers: Vec<PeerInfo>,
+}
+
+impl RegionInfo {
+    /// The id of the region, which stays the same as the region splits or changes peers.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// The first key of the region.
+    pub fn start_key(&self) -> &Key {
+        &self.start_key
+    }
+
+    /// The key after the last key of the region, or an empty key if the region is the last one.
+    pub fn end_key(&self) -> &Key {
+        &self.end_key
+    }
+
+    /// The epoch of the region's membership, incremented whenever a peer is added or removed.
+    pub fn conf_ver(&self) -> u64 {
+        self.conf_ver
+    }
+
+    /// The epoch of the region's range, incremented whenever the region is split or merged.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The leader of the region, unless it currently has none.
+    pub fn leader(&self) -> Option<&PeerInfo> {
+        self.leader.as_ref()
+    }
+
+    /// All peers of the region, including the leader.
+    pub fn peers(&self) -> &[PeerInfo] {
+        &self.peers
+    }
+}
+
+impl From<Region> for RegionInfo {
+    fn from(region: Region) -> RegionInfo {
+        let epoch = region.region.get_region_epoch();
+        RegionInfo {
+            id: region.id(),
+            start_key: region.start_key().to_vec().into(),
+            end_key: region.end_key().to_vec().into(),
+            conf_ver: epoch.get_conf_ver(),
+            version: epoch.get_version(),
+            leader: region.leader.as_ref().map(PeerInfo::from),
+            peers: region
+                .region
+                .get_peers()
+                .iter()
+                .map(PeerInfo::from)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_info() {
+        let mut meta = metapb::Region::default();
+        meta.set_id(7);
+        meta.set_start_key(b"a".to_vec());
+        meta.set_end_key(b"z".to_vec());
+        let mut epoch = metapb::RegionEpoch::default();
+        epoch.set_conf_ver(2);
+        epoch.set_version(3);
+        meta.set_region_epoch(epoch);
+        let mut peers = Vec::new();
+        for (id, store_id) in &[(1, 10), (2, 20)] {
+            let mut peer = metapb::Peer::default();
+            peer.set_id(*id);
+            peer.set_store_id(*store_id);
+            peers.push(peer);
+        }
+        meta.set_peers(peers.clone().into());
+        let region = RegionInfo::from(Region::new(meta, Some(peers[1].clone())));
+
+        assert_eq!(region.id(), 7);
+        assert_eq!(region.start_key(), &Key::from("a"));
+        assert_eq!(region.end_key(), &Key::from("z"));
+        assert_eq!((region.conf_ver(), region.version()), (2, 3));
+        assert_eq!(region.leader().map(PeerInfo::store_id), Some(20));
+        assert_eq!(region.peers().len(), 2);
+    }
+
+    #[test]
+    fn test_store_info() {
+        let mut store = metapb::Store::default();
+        store.set_id(1);
+        store.set_address("127.0.0.1:20160".to_owned());
+        store.set_state(metapb::StoreState::Offline);
+        let mut label = metapb::StoreLabel::default();
+        label.set_key("zone".to_owned());
+        label.set_value("z1".to_owned());
+        store.set_labels(vec![label].into());
+        let store = StoreInfo::from(store);
+
+        assert_eq!(store.address(), "127.0.0.1:20160");
+        assert_eq!(store.state(), StoreState::Offline);
+        assert_eq!(store.labels(), &[("zone".to_owned(), "z1".to_owned())]);
+    }
+}
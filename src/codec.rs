@@ -0,0 +1,218 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Encoding of typed keys and values.
+//!
+//! A [`TypedClient`](crate::raw::TypedClient) uses a [`KeyCodec`](KeyCodec) to turn keys into
+//! [`Key`](crate::Key)s and a [`Codec`](Codec) to turn values into bytes.
+//!
+//! Keys are encoded so that their ordering is preserved, which means range scans over typed keys
+//! visit them in order. Values are encoded with [serde](https://serde.rs), using either the
+//! [`JsonCodec`](JsonCodec), the [`BincodeCodec`](BincodeCodec), or your own [`Codec`](Codec).
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::mem;
+
+use crate::{Error, Key, Result};
+
+/// A serialization format for the values of a [`TypedClient`](crate::raw::TypedClient).
+///
+/// ```rust
+/// use tikv_client::codec::{BincodeCodec, Codec, JsonCodec};
+///
+/// let value = vec![String::from("TiKV"), String::from("Rust")];
+/// let json = JsonCodec::encode(&value).unwrap();
+/// assert_eq!(json, br#"["TiKV","Rust"]"#.to_vec());
+/// assert_eq!(JsonCodec::decode::<Vec<String>>(&json).unwrap(), value);
+///
+/// let bincode = BincodeCodec::encode(&value).unwrap();
+/// assert_eq!(BincodeCodec::decode::<Vec<String>>(&bincode).unwrap(), value);
+/// ```
+pub trait Codec {
+    /// Serialize a value into bytes.
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>>;
+    /// Deserialize a value from bytes.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T>;
+}
+
+/// A [`Codec`](Codec) which stores values as JSON.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        serde_json::to_vec(value).map_err(|e| Error::encode_error(e.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        serde_json::from_slice(bytes).map_err(|e| Error::decode_error(e.to_string()))
+    }
+}
+
+/// A [`Codec`](Codec) which stores values in the compact [bincode](https://github.com/servo/bincode)
+/// format.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BincodeCodec;
+
+impl Codec for BincodeCodec {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        bincode::serialize(value).map_err(|e| Error::encode_error(e.to_string()))
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        bincode::deserialize(bytes).map_err(|e| Error::decode_error(e.to_string()))
+    }
+}
+
+/// A type which can be used as the key of a [`TypedClient`](crate::raw::TypedClient).
+///
+/// Implementations must preserve ordering: if `a < b` then `a.encode_key() < b.encode_key()`.
+/// Integers are encoded big-endian (with the sign bit flipped for signed integers), strings and
+/// byte vectors are stored as they are.
+///
+/// ```rust
+/// use tikv_client::codec::KeyCodec;
+///
+/// assert!((-1i32).encode_key() < 1i32.encode_key());
+/// assert!(255u16.encode_key() < 256u16.encode_key());
+/// assert_eq!(i64::decode_key(&(-42i64).encode_key()).unwrap(), -42);
+/// ```
+pub trait KeyCodec: Sized {
+    /// Encode the key into a [`Key`](crate::Key).
+    fn encode_key(&self) -> Key;
+    /// Decode the key from the bytes of a [`Key`](crate::Key).
+    fn decode_key(key: &[u8]) -> Result<Self>;
+}
+
+fn fixed_size_bytes<'a>(key: &'a [u8], size: usize, type_name: &str) -> Result<&'a [u8]> {
+    if key.len() == size {
+        Ok(key)
+    } else {
+        Err(Error::decode_error(format!(
+            "expected {} bytes for a {} key, found {}",
+            size,
+            type_name,
+            key.len()
+        )))
+    }
+}
+
+macro_rules! unsigned_key_codec {
+    ($type:ty) => {
+        impl KeyCodec for $type {
+            fn encode_key(&self) -> Key {
+                self.to_be_bytes().to_vec().into()
+            }
+
+            fn decode_key(key: &[u8]) -> Result<Self> {
+                let size = mem::size_of::<$type>();
+                let mut bytes = [0; mem::size_of::<$type>()];
+                bytes.copy_from_slice(fixed_size_bytes(key, size, stringify!($type))?);
+                Ok(<$type>::from_be_bytes(bytes))
+            }
+        }
+    };
+}
+
+unsigned_key_codec!(u8);
+unsigned_key_codec!(u16);
+unsigned_key_codec!(u32);
+unsigned_key_codec!(u64);
+unsigned_key_codec!(u128);
+
+macro_rules! signed_key_codec {
+    ($type:ty, $unsigned:ty) => {
+        impl KeyCodec for $type {
+            fn encode_key(&self) -> Key {
+                // Flipping the sign bit orders negative numbers before positive ones.
+                let sign_bit: $unsigned = 1 << (mem::size_of::<$unsigned>() * 8 - 1);
+                ((*self as $unsigned) ^ sign_bit).encode_key()
+            }
+
+            fn decode_key(key: &[u8]) -> Result<Self> {
+                let sign_bit: $unsigned = 1 << (mem::size_of::<$unsigned>() * 8 - 1);
+                fixed_size_bytes(key, mem::size_of::<$type>(), stringify!($type))?;
+                Ok((<$unsigned>::decode_key(key)? ^ sign_bit) as $type)
+            }
+        }
+    };
+}
+
+signed_key_codec!(i8, u8);
+signed_key_codec!(i16, u16);
+signed_key_codec!(i32, u32);
+signed_key_codec!(i64, u64);
+signed_key_codec!(i128, u128);
+
+impl KeyCodec for String {
+    fn encode_key(&self) -> Key {
+        self.clone().into()
+    }
+
+    fn decode_key(key: &[u8]) -> Result<Self> {
+        String::from_utf8(key.to_vec()).map_err(|e| Error::decode_error(e.to_string()))
+    }
+}
+
+impl KeyCodec for Vec<u8> {
+    fn encode_key(&self) -> Key {
+        self.clone().into()
+    }
+
+    fn decode_key(key: &[u8]) -> Result<Self> {
+        Ok(key.to_vec())
+    }
+}
+
+impl KeyCodec for Key {
+    fn encode_key(&self) -> Key {
+        self.clone()
+    }
+
+    fn decode_key(key: &[u8]) -> Result<Self> {
+        Ok(key.to_vec().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn test_signed_key_order() {
+        let values = [i64::min_value(), -256, -1, 0, 1, 255, i64::max_value()];
+        for pair in values.windows(2) {
+            assert!(pair[0].encode_key() < pair[1].encode_key());
+        }
+        for value in values.iter() {
+            assert_eq!(i64::decode_key(&value.encode_key()).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn test_unsigned_key_order() {
+        let values = [0u32, 1, 255, 256, u32::max_value()];
+        for pair in values.windows(2) {
+            assert!(pair[0].encode_key() < pair[1].encode_key());
+        }
+        for value in values.iter() {
+            assert_eq!(u32::decode_key(&value.encode_key()).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        match u64::decode_key(&[1, 2, 3]).unwrap_err().kind() {
+            ErrorKind::Decode { .. } => {}
+            kind => panic!("unexpected error {:?}", kind),
+        }
+        match String::decode_key(&[0xFF]).unwrap_err().kind() {
+            ErrorKind::Decode { .. } => {}
+            kind => panic!("unexpected error {:?}", kind),
+        }
+        match JsonCodec::decode::<u64>(b"TiKV").unwrap_err().kind() {
+            ErrorKind::Decode { .. } => {}
+            kind => panic!("unexpected error {:?}", kind),
+        }
+    }
+}
@@ -1,7 +1,8 @@
 // Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
 
+use crate::rpc::{Runtime, RuntimeHandle};
 use serde_derive::{Deserialize, Serialize};
-use std::{path::PathBuf, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 /// The configuration for either a [`raw::Client`](super::raw::Client) or a
 /// [`transaction::Client`](super::transaction::Client).
@@ -20,7 +21,7 @@ use std::{path::PathBuf, time::Duration};
 /// parameters.
 ///
 /// TiKV does not currently offer encrypted storage (or encryption-at-rest).
-#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(default)]
 #[serde(rename_all = "kebab-case")]
 pub struct Config {
@@ -29,9 +30,67 @@ pub struct Config {
     pub(crate) cert_path: Option<PathBuf>,
     pub(crate) key_path: Option<PathBuf>,
     pub(crate) timeout: Duration,
+    pub(crate) raw_batch_max_keys: usize,
+    pub(crate) raw_batch_max_bytes: usize,
+    pub(crate) raw_batch_concurrency: usize,
+    pub(crate) replica_read: ReplicaReadMode,
+    pub(crate) max_retry_time: Duration,
+    pub(crate) batch_commands: bool,
+    pub(crate) batch_commands_wait: Duration,
+    pub(crate) connections_per_store: usize,
+    pub(crate) connection_idle_timeout: Duration,
+    pub(crate) max_in_flight_per_store: usize,
+    pub(crate) max_in_flight: usize,
+    pub(crate) queue_timeout: Duration,
+    pub(crate) tso_batch_wait: Duration,
+    pub(crate) tso_batch_max_size: usize,
+    pub(crate) tso_prefetch: usize,
+    #[serde(skip)]
+    pub(crate) runtime: Option<RuntimeHandle>,
+}
+
+/// Which peers of a region serve reads.
+///
+/// By default all requests are sent to the leader of a region, which makes the leaders of
+/// frequently read regions hot. Reading from followers spreads the load over all replicas. TiKV
+/// makes sure such reads are still linearizable, at the cost of an extra round trip between the
+/// follower and the leader.
+///
+/// If a read from a follower fails, it is retried on the leader. Writes always go to the leader.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplicaReadMode {
+    /// Read from the leader only.
+    Leader,
+    /// Read from the followers, or the leader if the region has no followers.
+    Follower,
+    /// Read from any of the leader and the followers.
+    LeaderAndFollower,
+}
+
+impl Default for ReplicaReadMode {
+    fn default() -> ReplicaReadMode {
+        ReplicaReadMode::Leader
+    }
 }
 
 const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const DEFAULT_RAW_BATCH_MAX_KEYS: usize = 4096;
+const DEFAULT_RAW_BATCH_MAX_BYTES: usize = 4 * 1024 * 1024;
+const DEFAULT_RAW_BATCH_CONCURRENCY: usize = 4;
+const DEFAULT_MAX_RETRY_TIME: Duration = Duration::from_secs(10);
+const DEFAULT_CONNECTIONS_PER_STORE: usize = 1;
+const DEFAULT_CONNECTION_IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+const DEFAULT_MAX_IN_FLIGHT_PER_STORE: usize = 1024;
+const DEFAULT_QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Default for Config {
+    /// A [`Config`](Config) without PD endpoints, whose other settings are the same as those of
+    /// [`Config::new`](Config::new). Deserialized configs fall back to it for missing fields.
+    fn default() -> Config {
+        Config::new(Vec::<String>::new())
+    }
+}
 
 impl Config {
     /// Create a new [`Config`](Config) which coordinates with the given PD endpoints.
@@ -50,6 +109,22 @@ impl Config {
             cert_path: None,
             key_path: None,
             timeout: DEFAULT_REQUEST_TIMEOUT,
+            raw_batch_max_keys: DEFAULT_RAW_BATCH_MAX_KEYS,
+            raw_batch_max_bytes: DEFAULT_RAW_BATCH_MAX_BYTES,
+            raw_batch_concurrency: DEFAULT_RAW_BATCH_CONCURRENCY,
+            replica_read: ReplicaReadMode::default(),
+            max_retry_time: DEFAULT_MAX_RETRY_TIME,
+            batch_commands: false,
+            batch_commands_wait: Duration::from_secs(0),
+            connections_per_store: DEFAULT_CONNECTIONS_PER_STORE,
+            connection_idle_timeout: DEFAULT_CONNECTION_IDLE_TIMEOUT,
+            max_in_flight_per_store: DEFAULT_MAX_IN_FLIGHT_PER_STORE,
+            max_in_flight: 0,
+            queue_timeout: DEFAULT_QUEUE_TIMEOUT,
+            tso_batch_wait: Duration::from_secs(0),
+            tso_batch_max_size: 0,
+            tso_prefetch: 0,
+            runtime: None,
         }
     }
 
@@ -80,4 +155,213 @@ impl Config {
         self.timeout = timeout;
         self
     }
+
+    /// Set the maximum number of keys and bytes sent to TiKV in a single RPC by raw batch writes.
+    ///
+    /// Larger batches are split into several RPCs so they stay below gRPC's message size limit
+    /// and TiKV's raft entry size limit. A limit of `0` means no limit.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .raw_batch_limits(1024, 1024 * 1024);
+    /// ```
+    pub fn raw_batch_limits(mut self, max_keys: usize, max_bytes: usize) -> Self {
+        self.raw_batch_max_keys = max_keys;
+        self.raw_batch_max_bytes = max_bytes;
+        self
+    }
+
+    /// Set how many RPCs of a single raw batch write may be in flight to each TiKV store at once.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .raw_batch_concurrency(8);
+    /// ```
+    pub fn raw_batch_concurrency(mut self, concurrency: usize) -> Self {
+        self.raw_batch_concurrency = concurrency;
+        self
+    }
+
+    /// Set how long a request is retried for.
+    ///
+    /// Requests failing because the region information they were sent with is outdated, or
+    /// because TiKV is busy, are retried with exponential backoff. No retry is made once it would
+    /// end later than `max_retry_time` after the request started. Set it to zero to disable
+    /// retries.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// # use std::time::Duration;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .max_retry_time(Duration::from_secs(30));
+    /// ```
+    pub fn max_retry_time(mut self, max_retry_time: Duration) -> Self {
+        self.max_retry_time = max_retry_time;
+        self
+    }
+
+    /// Set the [`ReplicaReadMode`](ReplicaReadMode) of reads. It can be overridden for a single
+    /// request.
+    ///
+    /// ```rust
+    /// # use tikv_client::{Config, ReplicaReadMode};
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .replica_read(ReplicaReadMode::Follower);
+    /// ```
+    pub fn replica_read(mut self, mode: ReplicaReadMode) -> Self {
+        self.replica_read = mode;
+        self
+    }
+
+    /// Send raw requests to each TiKV store over a single BatchCommands stream instead of as
+    /// separate RPCs.
+    ///
+    /// This reduces the per-request overhead of gRPC under a high request rate. Requests made
+    /// within `max_wait` of each other (or while the previous batch is sent) are coalesced into a
+    /// single message, trading latency for throughput. A `max_wait` of zero only coalesces the
+    /// requests which are already queued.
+    ///
+    /// If a store does not support BatchCommands, requests to it are sent as separate RPCs.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// # use std::time::Duration;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .batch_commands(Duration::from_millis(1));
+    /// ```
+    pub fn batch_commands(mut self, max_wait: Duration) -> Self {
+        self.batch_commands = true;
+        self.batch_commands_wait = max_wait;
+        self
+    }
+
+    /// Set the number of connections to each TiKV store. Requests to a store are spread over its
+    /// connections round-robin.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .connections_per_store(4);
+    /// ```
+    pub fn connections_per_store(mut self, connections: usize) -> Self {
+        self.connections_per_store = connections;
+        self
+    }
+
+    /// Set how long the connections to a TiKV store are kept open while no requests are sent to
+    /// it. They are reopened when the store is used again.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// # use std::time::Duration;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .connection_idle_timeout(Duration::from_secs(60));
+    /// ```
+    pub fn connection_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_idle_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of requests in flight to each TiKV store, and in total. A limit of
+    /// `0` means no limit.
+    ///
+    /// Requests beyond the limits wait in a queue until earlier requests finish, in the order they
+    /// were made. This keeps large batches from overloading a store.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .max_in_flight(256, 4096);
+    /// ```
+    pub fn max_in_flight(mut self, per_store: usize, total: usize) -> Self {
+        self.max_in_flight_per_store = per_store;
+        self.max_in_flight = total;
+        self
+    }
+
+    /// Set how long a request waits to be sent when too many requests are in flight, see
+    /// [`max_in_flight`](Config::max_in_flight). It then fails with
+    /// [`ErrorKind::QueueTimeout`](crate::ErrorKind::QueueTimeout).
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// # use std::time::Duration;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .queue_timeout(Duration::from_secs(1));
+    /// ```
+    pub fn queue_timeout(mut self, timeout: Duration) -> Self {
+        self.queue_timeout = timeout;
+        self
+    }
+
+    /// Set how requests for timestamps are batched into a single request to PD.
+    ///
+    /// While a request to PD is in flight, further requests for timestamps are collected and sent
+    /// as a batch once it is answered. Otherwise the first request of a batch waits up to
+    /// `max_wait` for more requests, or until the batch has `max_size` requests. A `max_wait` of
+    /// zero sends requests right away, and a `max_size` of `0` means no limit.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// # use std::time::Duration;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .tso_batch(Duration::from_micros(500), 256);
+    /// ```
+    pub fn tso_batch(mut self, max_wait: Duration, max_size: usize) -> Self {
+        self.tso_batch_wait = max_wait;
+        self.tso_batch_max_size = max_size;
+        self
+    }
+
+    /// Request `count` timestamps from PD ahead of time with every batch, and hand them out
+    /// without a round trip to PD until they are used up. `0` disables prefetching.
+    ///
+    /// The timestamps are still unique and increase monotonically, but a prefetched timestamp
+    /// may be older than one another client got from PD in the meantime. To bound how far behind
+    /// it can be, prefetched timestamps are discarded 3ms after PD allocated them. Only enable
+    /// this if the workload tolerates timestamps being that far out of order across clients.
+    ///
+    /// ```rust
+    /// # use tikv_client::Config;
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .tso_prefetch(64);
+    /// ```
+    pub fn tso_prefetch(mut self, count: usize) -> Self {
+        self.tso_prefetch = count;
+        self
+    }
+
+    /// Run the background tasks and timers of the client on `runtime`, rather than on a
+    /// [`DefaultRuntime`](crate::DefaultRuntime) with a thread of its own.
+    ///
+    /// ```rust
+    /// # use tikv_client::{Config, DefaultRuntime};
+    /// # use std::sync::Arc;
+    /// let runtime = Arc::new(DefaultRuntime::new().unwrap());
+    /// let config = Config::new(vec!["192.168.0.100:2379", "192.168.0.101:2379"])
+    ///     .runtime(runtime);
+    /// ```
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = Some(RuntimeHandle(runtime));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let config: Config = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.max_retry_time, DEFAULT_MAX_RETRY_TIME);
+        assert_eq!(config.queue_timeout, DEFAULT_QUEUE_TIMEOUT);
+        assert_eq!(
+            config.connection_idle_timeout,
+            DEFAULT_CONNECTION_IDLE_TIMEOUT
+        );
+    }
 }
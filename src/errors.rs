@@ -4,6 +4,7 @@ use failure::{Backtrace, Context, Fail};
 use grpcio;
 use std::fmt::{self, Display};
 use std::result;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct Error {
@@ -29,17 +30,22 @@ pub enum ErrorKind {
     // No region is found for the given key.
     #[fail(display = "Region is not found for key: {:?}", key)]
     RegionForKeyNotFound { key: Vec<u8> },
-    /// The peer is not the leader for the region.
+    /// The peer is not the leader for the region. TiKV includes the new leader if it knows it.
     #[fail(display = "Peer is not leader for region {}. {}", region_id, message)]
-    NotLeader { region_id: u64, message: String },
+    NotLeader {
+        region_id: u64,
+        leader: Option<kvproto::metapb::Peer>,
+        message: String,
+    },
     /// Stale epoch
     #[fail(display = "Stale epoch. {}", message)]
     StaleEpoch { message: String },
     /// No region is found for the given id.
     #[fail(display = "Region {} is not found. {}", region_id, message)]
     RegionNotFound { region_id: u64, message: String },
-    /// Invalid key range to scan. Only left bounded intervals are supported.
-    #[fail(display = "Only left bounded intervals are supported")]
+    /// Invalid key range. Only left bounded intervals whose start does not exceed their end are
+    /// supported.
+    #[fail(display = "Only left bounded intervals with start <= end are supported")]
     InvalidKeyRange,
     /// Cannot set an empty value
     #[fail(display = "Cannot set an empty value")]
@@ -90,6 +96,25 @@ pub enum ErrorKind {
         entry_size: u64,
         message: String,
     },
+    /// The request did not complete by its deadline.
+    #[fail(display = "Deadline exceeded")]
+    DeadlineExceeded,
+    /// Too many requests were in flight, and a request timed out waiting to be sent.
+    #[fail(
+        display = "Timed out after {:?} waiting to send a request, {} requests are in flight to {}",
+        timeout, limit, target
+    )]
+    QueueTimeout {
+        target: String,
+        limit: usize,
+        timeout: Duration,
+    },
+    /// A typed key or value could not be encoded.
+    #[fail(display = "Failed to encode: {}", message)]
+    Encode { message: String },
+    /// A typed key or value could not be decoded.
+    #[fail(display = "Failed to decode: {}", message)]
+    Decode { message: String },
     #[fail(display = "{}", message)]
     InternalError { message: String },
 }
@@ -115,6 +140,31 @@ impl Error {
         self.inner.get_context()
     }
 
+    /// Whether the error means the region information the request was sent with is outdated, so
+    /// the request should be retried after locating its keys again.
+    pub(crate) fn is_region_error(&self) -> bool {
+        match self.kind() {
+            ErrorKind::NotLeader { .. }
+            | ErrorKind::StaleEpoch { .. }
+            | ErrorKind::RegionNotFound { .. }
+            | ErrorKind::KeyNotInRegion { .. }
+            | ErrorKind::StaleCommand { .. }
+            | ErrorKind::StoreNotMatch { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Whether TiKV or PD rejected the RPC as unimplemented, which for a BatchCommands stream
+    /// means the store only serves unary requests.
+    pub(crate) fn is_grpc_unimplemented(&self) -> bool {
+        match self.kind() {
+            ErrorKind::Grpc(grpcio::Error::RpcFailure(status)) => {
+                status.status == grpcio::RpcStatusCode::Unimplemented
+            }
+            _ => false,
+        }
+    }
+
     pub(crate) fn unimplemented() -> Self {
         Error::from(ErrorKind::Unimplemented)
     }
@@ -123,9 +173,14 @@ impl Error {
         Error::from(ErrorKind::RegionForKeyNotFound { key })
     }
 
-    pub(crate) fn not_leader(region_id: u64, message: Option<String>) -> Self {
+    pub(crate) fn not_leader(
+        region_id: u64,
+        leader: Option<kvproto::metapb::Peer>,
+        message: Option<String>,
+    ) -> Self {
         Error::from(ErrorKind::NotLeader {
             region_id,
+            leader,
             message: message.unwrap_or_default(),
         })
     }
@@ -198,6 +253,26 @@ impl Error {
         })
     }
 
+    pub(crate) fn deadline_exceeded() -> Self {
+        Error::from(ErrorKind::DeadlineExceeded)
+    }
+
+    pub(crate) fn queue_timeout(target: String, limit: usize, timeout: Duration) -> Self {
+        Error::from(ErrorKind::QueueTimeout {
+            target,
+            limit,
+            timeout,
+        })
+    }
+
+    pub(crate) fn encode_error(message: String) -> Self {
+        Error::from(ErrorKind::Encode { message })
+    }
+
+    pub(crate) fn decode_error(message: String) -> Self {
+        Error::from(ErrorKind::Decode { message })
+    }
+
     pub(crate) fn internal_error(message: String) -> Self {
         Error::from(ErrorKind::InternalError { message })
     }
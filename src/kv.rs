@@ -68,6 +68,31 @@ impl Key {
     pub(crate) fn into_inner(self) -> Vec<u8> {
         self.0
     }
+
+    /// Get the exclusive upper bound of the keys which start with this key.
+    ///
+    /// This is the smallest key which is greater than every key starting with this key. If there
+    /// is no such key (because this key is empty or only contains `0xFF` bytes), the range of
+    /// keys with this prefix is unbounded and `None` is returned.
+    ///
+    /// ```rust
+    /// use tikv_client::Key;
+    ///
+    /// assert_eq!(Key::from("TiKV").prefix_next(), Some(Key::from("TiKW")));
+    /// assert_eq!(Key::from(vec![1, 0xFF]).prefix_next(), Some(Key::from(vec![2])));
+    /// assert_eq!(Key::from(vec![0xFF, 0xFF]).prefix_next(), None);
+    /// assert_eq!(Key::default().prefix_next(), None);
+    /// ```
+    pub fn prefix_next(&self) -> Option<Key> {
+        let mut next = self.0.clone();
+        while let Some(byte) = next.pop() {
+            if byte < u8::MAX {
+                next.push(byte + 1);
+                return Some(Key(next));
+            }
+        }
+        None
+    }
 }
 
 impl From<Vec<u8>> for Key {
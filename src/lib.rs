@@ -74,6 +74,8 @@
 //!
 //! At this point, you should seek the documentation in the related API modules.
 
+mod cluster;
+pub mod codec;
 mod compat;
 mod config;
 mod errors;
@@ -83,7 +85,9 @@ mod rpc;
 pub mod transaction;
 
 #[doc(inline)]
-pub use crate::config::Config;
+pub use crate::cluster::{ClusterInfo, PeerInfo, RegionInfo, StoreInfo, StoreState};
+#[doc(inline)]
+pub use crate::config::{Config, ReplicaReadMode};
 #[doc(inline)]
 pub use crate::errors::Error;
 #[doc(inline)]
@@ -92,3 +96,5 @@ pub use crate::errors::ErrorKind;
 pub use crate::errors::Result;
 #[doc(inline)]
 pub use crate::kv::{Key, KeyRange, KvPair, Value};
+#[doc(inline)]
+pub use crate::rpc::{DefaultRuntime, Runtime};
@@ -0,0 +1,157 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::{Error, Key, Result};
+use std::iter;
+
+/// The result of a raw batch request which may have partially failed.
+///
+/// A batch request is split by region and sent as several RPCs. The RPCs for some regions may fail
+/// while others succeed. A `BatchResult` lists what succeeded, and the keys of each failed RPC
+/// along with the error it failed with. Raw writes are idempotent, so the failed keys can simply be
+/// retried.
+///
+/// ```rust
+/// # use tikv_client::raw::BatchResult;
+/// # fn handle(result: BatchResult<tikv_client::Key>) {
+/// if !result.is_complete() {
+///     for (keys, error) in result.failed() {
+///         println!("{} keys failed: {}", keys.len(), error);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BatchResult<T> {
+    succeeded: Vec<T>,
+    failed: Vec<(Vec<Key>, Error)>,
+}
+
+impl<T> BatchResult<T> {
+    pub(crate) fn from_succeeded(succeeded: Vec<T>) -> Self {
+        BatchResult {
+            succeeded,
+            failed: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_failed(keys: Vec<Key>, error: Error) -> Self {
+        BatchResult {
+            succeeded: Vec::new(),
+            failed: vec![(keys, error)],
+        }
+    }
+
+    pub(crate) fn merge(&mut self, mut other: BatchResult<T>) {
+        self.succeeded.append(&mut other.succeeded);
+        self.failed.append(&mut other.failed);
+    }
+
+    pub(crate) fn map<U>(
+        self,
+        succeeded: impl FnMut(T) -> U,
+        failed_key: impl FnMut(Key) -> Key,
+    ) -> BatchResult<U> {
+        let mut failed_key = failed_key;
+        BatchResult {
+            succeeded: self.succeeded.into_iter().map(succeeded).collect(),
+            failed: self
+                .failed
+                .into_iter()
+                .map(|(keys, error)| (keys.into_iter().map(&mut failed_key).collect(), error))
+                .collect(),
+        }
+    }
+
+    /// What succeeded, in no particular order.
+    ///
+    /// For writes these are the keys which were written, for reads the pairs which were found.
+    pub fn succeeded(&self) -> &[T] {
+        &self.succeeded
+    }
+
+    /// The keys of each failed RPC, along with the error it failed with.
+    pub fn failed(&self) -> &[(Vec<Key>, Error)] {
+        &self.failed
+    }
+
+    /// All keys which failed.
+    pub fn failed_keys(&self) -> impl Iterator<Item = &Key> {
+        self.failed.iter().flat_map(|(keys, _)| keys.iter())
+    }
+
+    /// Whether no part of the batch failed.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Split into what succeeded, and the keys of each failed part with its error.
+    pub fn into_inner(self) -> (Vec<T>, Vec<(Vec<Key>, Error)>) {
+        (self.succeeded, self.failed)
+    }
+
+    /// Convert into what succeeded, or the first error if any part of the batch failed.
+    pub fn into_result(self) -> Result<Vec<T>> {
+        match self.failed.into_iter().next() {
+            Some((_, error)) => Err(error),
+            None => Ok(self.succeeded),
+        }
+    }
+}
+
+impl<T> Default for BatchResult<T> {
+    fn default() -> Self {
+        BatchResult::from_succeeded(Vec::new())
+    }
+}
+
+impl<T> Extend<BatchResult<T>> for BatchResult<T> {
+    fn extend<I: IntoIterator<Item = BatchResult<T>>>(&mut self, iter: I) {
+        for other in iter {
+            self.merge(other);
+        }
+    }
+}
+
+impl<T> iter::FromIterator<BatchResult<T>> for BatchResult<T> {
+    fn from_iter<I: IntoIterator<Item = BatchResult<T>>>(iter: I) -> Self {
+        let mut result = BatchResult::default();
+        result.extend(iter);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_result() {
+        let result: BatchResult<Key> = vec![
+            BatchResult::from_succeeded(vec!["a".into(), "b".into()]),
+            BatchResult::from_failed(vec!["c".into()], Error::unimplemented()),
+            BatchResult::from_succeeded(vec!["d".into()]),
+        ]
+        .into_iter()
+        .collect();
+        assert!(!result.is_complete());
+        assert_eq!(result.succeeded().len(), 3);
+        assert_eq!(
+            result.failed_keys().collect::<Vec<_>>(),
+            vec![&Key::from("c")]
+        );
+
+        let result = result.map(
+            |key| Key::from(key[..].to_ascii_uppercase()),
+            |key| Key::from(key[..].to_ascii_uppercase()),
+        );
+        assert_eq!(
+            result.failed_keys().collect::<Vec<_>>(),
+            vec![&Key::from("C")]
+        );
+        assert!(result.into_result().is_err());
+
+        let result = BatchResult::from_succeeded(vec![Key::from("a")]);
+        assert!(result.is_complete());
+        assert_eq!(result.into_result().unwrap(), vec![Key::from("a")]);
+    }
+}
@@ -0,0 +1,2174 @@
+// Copyright 2018 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Raw related functionality.
+//!
+//! Using the [`raw::Client`](raw::Client) you can utilize TiKV's raw interface.
+//!
+//! This interface offers optimal performance as it does not require coordination with a timestamp
+//! oracle, while the transactional interface does.
+//!
+//! **Warning:** It is not advisable to use both raw and transactional functionality in the same keyspace.
+//!
+pub use self::{batch::BatchResult, typed::TypedClient};
+
+use crate::{
+    rpc::{with_deadline, RpcClient, Runtime},
+    ClusterInfo, Config, Error, Key, KeyRange, KvPair, ReplicaReadMode, Result, Value,
+};
+use futures::{future, prelude::TryFutureExt, task::Context, Future, Poll};
+use std::{
+    fmt,
+    ops::Bound,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+    u32,
+};
+
+mod batch;
+mod typed;
+
+const MAX_RAW_KV_SCAN_LIMIT: u32 = 10240;
+
+/// The TiKV raw [`Client`](Client) is used to issue requests to the TiKV server and PD cluster.
+pub struct Client {
+    rpc: Arc<RpcClient>,
+    prefix: Key,
+}
+
+impl Client {
+    /// Create a new [`Client`](Client) once the [`Connect`](Connect) resolves.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// let connect = Client::new(Config::default());
+    /// let client = connect.await.unwrap();
+    /// # });
+    /// ```
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(config: Config) -> Connect {
+        Connect::new(config)
+    }
+
+    /// Create a view of the [`Client`](Client) which is isolated to the keys starting with the
+    /// given prefix.
+    ///
+    /// The prefix is prepended to every key and range given to the view, and stripped from the
+    /// keys of the returned [`KvPair`](KvPair)s. Unbounded ranges are clamped to the keys starting
+    /// with the prefix, so the view can never read or modify keys outside of it. Creating a view
+    /// of a view concatenates the prefixes.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{KvPair, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let tenant = connected_client.with_prefix("tenant_a/");
+    /// // Sets the key `tenant_a/TiKV`.
+    /// tenant.put("TiKV", "Rust").await.unwrap();
+    /// // Only scans the keys starting with `tenant_a/`.
+    /// let result: Vec<KvPair> = tenant.scan(.., 10).await.unwrap();
+    /// assert_eq!(result, vec![KvPair::new("TiKV", "Rust")]);
+    /// # });
+    /// ```
+    pub fn with_prefix(&self, prefix: impl Into<Key>) -> Client {
+        Client {
+            rpc: self.rpc(),
+            prefix: self.prefixed(prefix),
+        }
+    }
+
+    #[inline]
+    fn rpc(&self) -> Arc<RpcClient> {
+        Arc::clone(&self.rpc)
+    }
+
+    fn prefixed(&self, key: impl Into<Key>) -> Key {
+        prefixed_key(&self.prefix, key.into())
+    }
+
+    fn prefixed_range(&self, range: impl KeyRange) -> (Bound<Key>, Bound<Key>) {
+        prefixed_range(&self.prefix, range)
+    }
+
+    /// Create a new [`Get`](Get) request.
+    ///
+    /// Once resolved this request will result in the fetching of the value associated with the
+    /// given key.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Value, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let key = "TiKV";
+    /// let req = connected_client.get(key);
+    /// let result: Option<Value> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn get(&self, key: impl Into<Key>) -> Get {
+        Get::new(self.rpc(), GetInner::new(self.prefixed(key)))
+    }
+
+    /// Create a new [`BatchGet`](BatchGet) request.
+    ///
+    /// Once resolved this request will result in the fetching of the values associated with the
+    /// given keys.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{KvPair, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let keys = vec!["TiKV", "TiDB"];
+    /// let req = connected_client.batch_get(keys);
+    /// let result: Vec<KvPair> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_get(&self, keys: impl IntoIterator<Item = impl Into<Key>>) -> BatchGet {
+        BatchGet::new(
+            self.rpc(),
+            BatchGetInner::new(
+                keys.into_iter().map(|key| self.prefixed(key)).collect(),
+                self.prefix.clone(),
+            ),
+        )
+    }
+
+    /// Create a new [`BatchGetPartial`](BatchGetPartial) request.
+    ///
+    /// Like [`batch_get`](Client::batch_get), but if reading some of the keys fails, the keys
+    /// which were read are still returned, along with the keys which failed and why.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{KvPair, Config, raw::{BatchResult, Client}};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let keys = vec!["TiKV", "TiDB"];
+    /// let req = connected_client.batch_get_partial(keys);
+    /// let result: BatchResult<KvPair> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_get_partial(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<Key>>,
+    ) -> BatchGetPartial {
+        BatchGetPartial::new(
+            self.rpc(),
+            BatchGetPartialInner::new(
+                keys.into_iter().map(|key| self.prefixed(key)).collect(),
+                self.prefix.clone(),
+            ),
+        )
+    }
+
+    /// Create a new [`BatchGetOrdered`](BatchGetOrdered) request.
+    ///
+    /// Once resolved this request will result in the fetching of the values associated with the
+    /// given keys. Unlike [`batch_get`](Client::batch_get), the result holds one entry per given
+    /// key (including duplicates), in the same order as the keys, with `None` for missing keys.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Value, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let keys = vec!["TiKV", "TiDB"];
+    /// let req = connected_client.batch_get_ordered(keys);
+    /// let result: Vec<Option<Value>> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_get_ordered(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<Key>>,
+    ) -> BatchGetOrdered {
+        BatchGetOrdered::new(
+            self.rpc(),
+            BatchGetOrderedInner::new(keys.into_iter().map(|key| self.prefixed(key)).collect()),
+        )
+    }
+
+    /// Create a new [`Put`](Put) request.
+    ///
+    /// Once resolved this request will result in the setting of the value associated with the given key.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Key, Value, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let key = "TiKV";
+    /// let val = "TiKV";
+    /// let req = connected_client.put(key, val);
+    /// let result: () = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn put(&self, key: impl Into<Key>, value: impl Into<Value>) -> Put {
+        Put::new(self.rpc(), PutInner::new(self.prefixed(key), value.into()))
+    }
+
+    /// Create a new [`BatchPut`](BatchPut) request.
+    ///
+    /// Once resolved this request will result in the setting of the value associated with the given key.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Error, Result, KvPair, Key, Value, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let kvpair1 = ("PD", "Go");
+    /// let kvpair2 = ("TiKV", "Rust");
+    /// let iterable = vec![kvpair1, kvpair2];
+    /// let req = connected_client.batch_put(iterable);
+    /// let result: () = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_put(&self, pairs: impl IntoIterator<Item = impl Into<KvPair>>) -> BatchPut {
+        BatchPut::new(
+            self.rpc(),
+            BatchPutInner::new(
+                pairs
+                    .into_iter()
+                    .map(|pair| {
+                        let (key, value) = Into::<KvPair>::into(pair).into_inner();
+                        KvPair::new(self.prefixed(key), value)
+                    })
+                    .collect(),
+            ),
+        )
+    }
+
+    /// Create a new [`BatchPutPartial`](BatchPutPartial) request.
+    ///
+    /// Like [`batch_put`](Client::batch_put), but if writing some of the pairs fails, the result
+    /// lists the keys which were written and the keys which failed and why. Only the failed keys
+    /// need to be retried.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Key, Config, raw::{BatchResult, Client}};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let iterable = vec![("PD", "Go"), ("TiKV", "Rust")];
+    /// let req = connected_client.batch_put_partial(iterable);
+    /// let result: BatchResult<Key> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_put_partial(
+        &self,
+        pairs: impl IntoIterator<Item = impl Into<KvPair>>,
+    ) -> BatchPutPartial {
+        BatchPutPartial::new(
+            self.rpc(),
+            BatchPutPartialInner::new(
+                pairs
+                    .into_iter()
+                    .map(|pair| {
+                        let (key, value) = Into::<KvPair>::into(pair).into_inner();
+                        KvPair::new(self.prefixed(key), value)
+                    })
+                    .collect(),
+                self.prefix.clone(),
+            ),
+        )
+    }
+
+    /// Create a new [`Delete`](Delete) request.
+    ///
+    /// Once resolved this request will result in the deletion of the given key.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Key, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let key = "TiKV";
+    /// let req = connected_client.delete(key);
+    /// let result: () = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn delete(&self, key: impl Into<Key>) -> Delete {
+        Delete::new(self.rpc(), DeleteInner::new(self.prefixed(key)))
+    }
+
+    /// Create a new [`BatchDelete`](BatchDelete) request.
+    ///
+    /// Once resolved this request will result in the deletion of the given keys.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let keys = vec!["TiKV", "TiDB"];
+    /// let req = connected_client.batch_delete(keys);
+    /// let result: () = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_delete(&self, keys: impl IntoIterator<Item = impl Into<Key>>) -> BatchDelete {
+        BatchDelete::new(
+            self.rpc(),
+            BatchDeleteInner::new(keys.into_iter().map(|key| self.prefixed(key)).collect()),
+        )
+    }
+
+    /// Create a new [`BatchDeletePartial`](BatchDeletePartial) request.
+    ///
+    /// Like [`batch_delete`](Client::batch_delete), but if deleting some of the keys fails, the
+    /// result lists the keys which were deleted and the keys which failed and why.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Key, Config, raw::{BatchResult, Client}};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let keys = vec!["TiKV", "TiDB"];
+    /// let req = connected_client.batch_delete_partial(keys);
+    /// let result: BatchResult<Key> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn batch_delete_partial(
+        &self,
+        keys: impl IntoIterator<Item = impl Into<Key>>,
+    ) -> BatchDeletePartial {
+        BatchDeletePartial::new(
+            self.rpc(),
+            BatchDeletePartialInner::new(
+                keys.into_iter().map(|key| self.prefixed(key)).collect(),
+                self.prefix.clone(),
+            ),
+        )
+    }
+
+    /// Create a new [`Scan`](Scan) request.
+    ///
+    /// Once resolved this request will result in a scanner over the given keys.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{KvPair, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let inclusive_range = "TiKV"..="TiDB";
+    /// let req = connected_client.scan(inclusive_range, 2);
+    /// let result: Vec<KvPair> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn scan(&self, range: impl KeyRange, limit: u32) -> Scan {
+        Scan::new(
+            self.rpc(),
+            ScanInner::new(self.prefixed_range(range), limit, self.prefix.clone()),
+        )
+    }
+
+    /// Create a new [`Scan`](Scan) request over the keys starting with the given prefix.
+    ///
+    /// Once resolved this request will result in a scanner over up to `limit` keys starting with
+    /// `prefix`.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{KvPair, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let req = connected_client.scan_prefix("Ti", 10);
+    /// let result: Vec<KvPair> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn scan_prefix(&self, prefix: impl Into<Key>, limit: u32) -> Scan {
+        self.scan(prefix_range(prefix.into()), limit)
+    }
+
+    /// Create a new [`BatchScan`](BatchScan) request.
+    ///
+    /// Once resolved this request will result in a set of scanners over the given keys.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Key, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let inclusive_range1 = "TiDB"..="TiKV";
+    /// let inclusive_range2 = "TiKV"..="TiSpark";
+    /// let iterable = vec![inclusive_range1, inclusive_range2];
+    /// let req = connected_client.batch_scan(iterable, 2);
+    /// let result = req.await;
+    /// # });
+    /// ```
+    pub fn batch_scan(
+        &self,
+        ranges: impl IntoIterator<Item = impl KeyRange>,
+        each_limit: u32,
+    ) -> BatchScan {
+        BatchScan::new(
+            self.rpc(),
+            BatchScanInner::new(
+                ranges
+                    .into_iter()
+                    .map(|range| self.prefixed_range(range).into_keys())
+                    .collect(),
+                each_limit,
+                self.prefix.clone(),
+            ),
+        )
+    }
+
+    /// Create a new [`DeleteRange`](DeleteRange) request.
+    ///
+    /// Once resolved this request will result in the deletion of all keys over the given range,
+    /// and report the number of regions which were cleared. The range may be unbounded at the end.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Key, Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let inclusive_range = "TiDB"..="TiKV";
+    /// let req = connected_client.delete_range(inclusive_range);
+    /// let cleared_regions: usize = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn delete_range(&self, range: impl KeyRange) -> DeleteRange {
+        DeleteRange::new(
+            self.rpc(),
+            DeleteRangeInner::new(self.prefixed_range(range).into_keys()),
+        )
+    }
+
+    /// Create a new [`DeleteRange`](DeleteRange) request over the keys starting with the given
+    /// prefix.
+    ///
+    /// Once resolved this request will result in the deletion of all keys starting with `prefix`,
+    /// and report the number of regions which were cleared.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let req = connected_client.delete_prefix("Ti");
+    /// let cleared_regions: usize = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn delete_prefix(&self, prefix: impl Into<Key>) -> DeleteRange {
+        self.delete_range(prefix_range(prefix.into()))
+    }
+
+    /// Create a new [`PrefetchRegions`](PrefetchRegions) request.
+    ///
+    /// Once resolved this request will have loaded the locations of all regions overlapping the
+    /// given range into the client's region cache, and report the number of regions. Later
+    /// requests in the range then don't need to ask PD where their keys are, which is useful to
+    /// warm up the client before a burst of requests.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let req = connected_client.prefetch_regions("TiDB".."TiKV");
+    /// let regions: usize = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn prefetch_regions(&self, range: impl KeyRange) -> PrefetchRegions {
+        PrefetchRegions::new(
+            self.rpc(),
+            PrefetchRegionsInner::new(self.prefixed_range(range).into_keys()),
+        )
+    }
+
+    /// Create a new [`SplitRange`](SplitRange) request.
+    ///
+    /// Once resolved this request will result in at most `max_chunks` contiguous sub-ranges which
+    /// together cover the given range, each as a start key and an optional end key. The sub-ranges
+    /// are aligned to the current region boundaries, neighbouring regions being merged into one
+    /// sub-range when there are more regions than `max_chunks`. This is useful to spread a job
+    /// over a range, e.g. a full scan, across parallel workers which each talk to few regions.
+    ///
+    /// The regions may be split or merged at any time, so the sub-ranges are only a hint of where
+    /// the boundaries are, requests in a sub-range are still correct if they changed.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client, Key};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let req = connected_client.split_range("TiDB".."TiKV", 8);
+    /// let chunks: Vec<(Key, Option<Key>)> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn split_range(&self, range: impl KeyRange, max_chunks: usize) -> SplitRange {
+        SplitRange::new(
+            self.rpc(),
+            SplitRangeInner::new(
+                self.prefixed_range(range).into_keys(),
+                max_chunks,
+                self.prefix.clone(),
+            ),
+        )
+    }
+
+    /// Create a new [`SplitRegions`](SplitRegions) request.
+    ///
+    /// Once resolved this request will have split the regions containing the given keys at those
+    /// keys, and result in the ids of the newly created regions. Splitting the key space before a
+    /// bulk load, and then [scattering](Client::scatter_region) the new regions, spreads the
+    /// writes across the stores of the cluster instead of overloading the store of one region.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let req = connected_client.split_regions(vec!["TiDB", "TiKV"]);
+    /// let new_regions: Vec<u64> = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn split_regions(
+        &self,
+        split_keys: impl IntoIterator<Item = impl Into<Key>>,
+    ) -> SplitRegions {
+        SplitRegions::new(
+            self.rpc(),
+            SplitRegionsInner::new(
+                split_keys
+                    .into_iter()
+                    .map(|key| self.prefixed(key))
+                    .collect(),
+            ),
+        )
+    }
+
+    /// Create a new [`ScatterRegion`](ScatterRegion) request.
+    ///
+    /// Once resolved this request will have asked PD to move the peers and the leader of the
+    /// given region to randomly chosen stores. PD moves them in the background, use
+    /// [`wait_for_scatter`](Client::wait_for_scatter) to wait until it is done.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # use std::time::{Duration, Instant};
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// for region_id in connected_client.split_regions(vec!["TiDB", "TiKV"]).await.unwrap() {
+    ///     connected_client.scatter_region(region_id).await.unwrap();
+    ///     connected_client
+    ///         .wait_for_scatter(region_id)
+    ///         .deadline(Instant::now() + Duration::from_secs(60))
+    ///         .await
+    ///         .unwrap();
+    /// }
+    /// # });
+    /// ```
+    pub fn scatter_region(&self, region_id: u64) -> ScatterRegion {
+        ScatterRegion::new(self.rpc(), ScatterRegionInner::new(region_id))
+    }
+
+    /// Create a new [`WaitForScatter`](WaitForScatter) request.
+    ///
+    /// Once resolved PD has finished scattering the given region, or has given up on it, see
+    /// [`scatter_region`](Client::scatter_region). Scattering a region can take a while, so you
+    /// will usually want to set a longer [deadline](WaitForScatter::deadline) than the default.
+    pub fn wait_for_scatter(&self, region_id: u64) -> WaitForScatter {
+        WaitForScatter::new(self.rpc(), WaitForScatterInner::new(region_id))
+    }
+
+    /// Create a new [`GetGcSafePoint`](GetGcSafePoint) request.
+    ///
+    /// Once resolved this request will result in the GC safe point of the cluster: TiKV may
+    /// garbage collect versions of data older than this timestamp.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let safe_point: u64 = connected_client.get_gc_safe_point().await.unwrap();
+    /// # });
+    /// ```
+    pub fn get_gc_safe_point(&self) -> GetGcSafePoint {
+        GetGcSafePoint::new(self.rpc(), GetGcSafePointInner::new())
+    }
+
+    /// Create a new [`UpdateGcSafePoint`](UpdateGcSafePoint) request.
+    ///
+    /// Once resolved this request will have advanced the GC safe point to the given timestamp,
+    /// and result in the new safe point. PD never moves the safe point back, so the new safe
+    /// point is the current one if it is newer than the requested one.
+    ///
+    /// The caller is responsible for not passing the safe points of services, see
+    /// [`update_service_gc_safe_point`](Client::update_service_gc_safe_point).
+    pub fn update_gc_safe_point(&self, safe_point: u64) -> UpdateGcSafePoint {
+        UpdateGcSafePoint::new(self.rpc(), UpdateGcSafePointInner::new(safe_point))
+    }
+
+    /// Create a new [`UpdateServiceGcSafePoint`](UpdateServiceGcSafePoint) request.
+    ///
+    /// Once resolved this request will have set the safe point of the given service, e.g. a
+    /// backup or CDC task, and result in the minimum safe point of all services. The GC worker
+    /// will not advance the GC safe point past the safe point of the service for the given `ttl`,
+    /// so data the service still needs is not garbage collected underneath it.
+    ///
+    /// PD counts the `ttl` in whole seconds, so it is rounded up to at least a second. A service
+    /// keeps its safe point by updating it before the `ttl` expires, and removes it with
+    /// [`remove_service_gc_safe_point`](Client::remove_service_gc_safe_point) once it is done.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # use std::time::Duration;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// # let backup_ts = 0;
+    /// let req = connected_client.update_service_gc_safe_point(
+    ///     "backup",
+    ///     Duration::from_secs(300),
+    ///     backup_ts,
+    /// );
+    /// let min_safe_point: u64 = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn update_service_gc_safe_point(
+        &self,
+        service_id: impl Into<String>,
+        ttl: Duration,
+        safe_point: u64,
+    ) -> UpdateServiceGcSafePoint {
+        UpdateServiceGcSafePoint::new(
+            self.rpc(),
+            UpdateServiceGcSafePointInner::new(service_id.into(), ttl, safe_point),
+        )
+    }
+
+    /// Create a new [`RemoveServiceGcSafePoint`](RemoveServiceGcSafePoint) request.
+    ///
+    /// Once resolved this request will have removed the safe point of the given service, so it
+    /// no longer holds back the GC safe point.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let req = connected_client.remove_service_gc_safe_point("backup");
+    /// let result: () = req.await.unwrap();
+    /// # });
+    /// ```
+    pub fn remove_service_gc_safe_point(
+        &self,
+        service_id: impl Into<String>,
+    ) -> RemoveServiceGcSafePoint {
+        RemoveServiceGcSafePoint::new(
+            self.rpc(),
+            RemoveServiceGcSafePointInner::new(service_id.into()),
+        )
+    }
+
+    /// The metadata of the cluster, i.e. its stores and regions, see
+    /// [`ClusterInfo`](crate::ClusterInfo). The keys looked up are prefixed like those of the
+    /// requests of this client.
+    ///
+    /// ```rust,no_run
+    /// # #![feature(async_await)]
+    /// # use tikv_client::{Config, raw::Client, RegionInfo};
+    /// # use futures::prelude::*;
+    /// # futures::executor::block_on(async {
+    /// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+    /// # let connected_client = connecting_client.await.unwrap();
+    /// let region: RegionInfo = connected_client.cluster_info().region("TiKV").await.unwrap();
+    /// # });
+    /// ```
+    pub fn cluster_info(&self) -> ClusterInfo {
+        ClusterInfo::new(self.rpc(), self.prefix.clone())
+    }
+}
+
+/// `key` in the key space of `prefix`.
+pub(crate) fn prefixed_key(prefix: &Key, key: Key) -> Key {
+    if prefix.is_empty() {
+        return key;
+    }
+    let mut prefixed = prefix.clone().into_inner();
+    prefixed.extend_from_slice(&key);
+    prefixed.into()
+}
+
+/// `range` in the key space of `prefix`, where unbounded ends are bounded by the prefix.
+pub(crate) fn prefixed_range(prefix: &Key, range: impl KeyRange) -> (Bound<Key>, Bound<Key>) {
+    let (start, end) = range.into_bounds();
+    if prefix.is_empty() {
+        return (start, end);
+    }
+    let start = match start {
+        Bound::Included(key) => Bound::Included(prefixed_key(prefix, key)),
+        Bound::Excluded(key) => Bound::Excluded(prefixed_key(prefix, key)),
+        Bound::Unbounded => Bound::Included(prefix.clone()),
+    };
+    let end = match end {
+        Bound::Included(key) => Bound::Included(prefixed_key(prefix, key)),
+        Bound::Excluded(key) => Bound::Excluded(prefixed_key(prefix, key)),
+        Bound::Unbounded => match prefix.prefix_next() {
+            Some(key) => Bound::Excluded(key),
+            None => Bound::Unbounded,
+        },
+    };
+    (start, end)
+}
+
+/// The range of keys which start with `prefix`.
+fn prefix_range(prefix: Key) -> (Bound<Key>, Bound<Key>) {
+    let end = match prefix.prefix_next() {
+        Some(end) => Bound::Excluded(end),
+        None => Bound::Unbounded,
+    };
+    (Bound::Included(prefix), end)
+}
+
+/// An unresolved [`Client`](Client) connection to a TiKV cluster.
+///
+/// Once resolved it will result in a connected [`Client`](Client).
+///
+/// ```rust,no_run
+/// # #![feature(async_await)]
+/// use tikv_client::{Config, raw::{Client, Connect}};
+/// use futures::prelude::*;
+///
+/// # futures::executor::block_on(async {
+/// let connect: Connect = Client::new(Config::default());
+/// let client: Client = connect.await.unwrap();
+/// # });
+/// ```
+pub struct Connect {
+    config: Config,
+}
+
+impl Connect {
+    fn new(config: Config) -> Self {
+        Connect { config }
+    }
+}
+
+impl Future for Connect {
+    type Output = Result<Client>;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Self::Output> {
+        let config = &self.config;
+        let rpc = Arc::new(RpcClient::connect(config)?);
+        Poll::Ready(Ok(Client {
+            rpc,
+            prefix: Key::default(),
+        }))
+    }
+}
+
+/// A [`ColumnFamily`](ColumnFamily) is an optional parameter for [`raw::Client`](Client) requests.
+///
+/// TiKV uses RocksDB's `ColumnFamily` support. You can learn more about RocksDB's `ColumnFamily`s [on their wiki](https://github.com/facebook/rocksdb/wiki/Column-Families).
+///
+/// By default in TiKV data is stored in three different `ColumnFamily` values, configurable in the TiKV server's configuration:
+///
+/// * Default: Where real user data is stored. Set by `[rocksdb.defaultcf]`.
+/// * Write: Where MVCC and index related data are stored. Set by `[rocksdb.writecf]`.
+/// * Lock: Where lock information is stored. Set by `[rocksdb.lockcf]`.
+///
+/// Not providing a call a `ColumnFamily` means it will use the default value of `default`.
+///
+/// The best (and only) way to create a [`ColumnFamily`](ColumnFamily) is via the `From` implementation:
+///
+/// ```rust
+/// # use tikv_client::raw::ColumnFamily;
+///
+/// let cf = ColumnFamily::from("write");
+/// let cf = ColumnFamily::from(String::from("write"));
+/// let cf = ColumnFamily::from(&String::from("write"));
+/// ```
+///
+/// **But, you should not need to worry about all this:** Many functions which accept a
+/// `ColumnFamily` accept an `Into<ColumnFamily>`, which means all of the above types can be passed
+/// directly to those functions.
+#[derive(Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ColumnFamily(String);
+
+impl<T: Into<String>> From<T> for ColumnFamily {
+    fn from(i: T) -> ColumnFamily {
+        ColumnFamily(i.into())
+    }
+}
+
+impl fmt::Display for ColumnFamily {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The priority TiKV schedules a raw request with.
+///
+/// Lower priority requests are only processed once there are no higher priority requests waiting,
+/// which keeps bulk jobs from slowing down latency sensitive requests. Requests have
+/// [`Normal`](CommandPri::Normal) priority by default.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CommandPri {
+    /// For bulk jobs, e.g. imports or full scans, which should not slow down other requests.
+    Low,
+    /// For ordinary requests.
+    Normal,
+    /// For latency sensitive requests, which are processed before all others.
+    High,
+}
+
+impl Default for CommandPri {
+    fn default() -> CommandPri {
+        CommandPri::Normal
+    }
+}
+
+/// The options of a single raw request, set through the builder methods of the request.
+#[derive(Clone, Default)]
+pub(crate) struct RequestOptions {
+    pub(crate) cf: Option<ColumnFamily>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) deadline: Option<Instant>,
+    pub(crate) priority: CommandPri,
+    pub(crate) not_fill_cache: bool,
+    pub(crate) replica_read: Option<ReplicaReadMode>,
+}
+
+type BoxTryFuture<Resp> = Box<dyn Future<Output = Result<Resp>> + Send>;
+
+/// Strip the prefix of a prefixed [`Client`](Client) view from the keys of the given pairs.
+fn strip_prefix(prefix: &Key, mut pairs: Vec<KvPair>) -> Vec<KvPair> {
+    if !prefix.is_empty() {
+        for pair in &mut pairs {
+            strip_key_prefix(prefix, pair.key_mut());
+        }
+    }
+    pairs
+}
+
+fn strip_key_prefix(prefix: &Key, key: &mut Key) {
+    if !prefix.is_empty() && key.starts_with(prefix) {
+        *key = key[prefix.len()..].to_vec().into();
+    }
+}
+
+/// Strip the prefix of a prefixed [`Client`](Client) view from the given sub-ranges. End keys
+/// outside of the prefix can only be the end of the view, so they become unbounded.
+fn strip_range_prefix(
+    prefix: &Key,
+    mut ranges: Vec<(Key, Option<Key>)>,
+) -> Vec<(Key, Option<Key>)> {
+    if !prefix.is_empty() {
+        for (start_key, end_key) in &mut ranges {
+            strip_key_prefix(prefix, start_key);
+            match end_key {
+                Some(key) if key.starts_with(prefix) => strip_key_prefix(prefix, key),
+                _ => *end_key = None,
+            }
+        }
+    }
+    ranges
+}
+
+fn strip_batch_result_prefix<T>(
+    prefix: &Key,
+    result: BatchResult<T>,
+    mut key_mut: impl FnMut(&mut T) -> &mut Key,
+) -> BatchResult<T> {
+    result.map(
+        |mut succeeded| {
+            strip_key_prefix(prefix, key_mut(&mut succeeded));
+            succeeded
+        },
+        |mut key| {
+            strip_key_prefix(prefix, &mut key);
+            key
+        },
+    )
+}
+
+trait RequestInner: Sized {
+    type Resp;
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<Self::Resp>;
+}
+
+enum RequestState<Inner>
+where
+    Inner: RequestInner,
+{
+    Uninitiated(Option<(Arc<RpcClient>, Inner, RequestOptions)>),
+    Initiated(BoxTryFuture<Inner::Resp>),
+}
+
+impl<Inner> RequestState<Inner>
+where
+    Inner: RequestInner,
+{
+    fn new(client: Arc<RpcClient>, inner: Inner) -> Self {
+        RequestState::Uninitiated(Some((client, inner, RequestOptions::default())))
+    }
+
+    fn options_mut(&mut self) -> Option<&mut RequestOptions> {
+        match self {
+            RequestState::Uninitiated(Some((_, _, ref mut options))) => Some(options),
+            _ => None,
+        }
+    }
+
+    fn inner_mut(&mut self) -> Option<&mut Inner> {
+        match self {
+            RequestState::Uninitiated(Some((_, ref mut inner, _))) => Some(inner),
+            _ => None,
+        }
+    }
+
+    fn assure_initialized<'a>(self: Pin<&'a mut Self>) -> Pin<&'a mut Self> {
+        unsafe {
+            let mut this = Pin::get_unchecked_mut(self);
+            if let RequestState::Uninitiated(state) = &mut this {
+                let (client, inner, mut options) = state.take().unwrap();
+                let deadline = *options
+                    .deadline
+                    .get_or_insert_with(|| client.default_deadline());
+                let timer = client.runtime().delay(deadline);
+                *this = RequestState::Initiated(Box::new(with_deadline(
+                    inner.execute(client, options),
+                    timer,
+                )));
+            }
+            Pin::new_unchecked(this)
+        }
+    }
+
+    fn assert_init_pin_mut<'a>(
+        self: Pin<&'a mut Self>,
+    ) -> Pin<&'a mut dyn Future<Output = Result<Inner::Resp>>> {
+        unsafe {
+            match Pin::get_unchecked_mut(self) {
+                RequestState::Initiated(future) => Pin::new_unchecked(&mut **future),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<Inner::Resp>> {
+        self = self.assure_initialized();
+        self.assert_init_pin_mut().poll(cx)
+    }
+}
+
+/// Implement the builder method which sets the deadline of a request.
+macro_rules! deadline_option {
+    ($type:ty) => {
+        impl $type {
+            /// Set the deadline of this request. All region lookups, retries and RPCs made by the
+            /// request must complete by then, or it fails with
+            /// [`ErrorKind::DeadlineExceeded`](crate::ErrorKind::DeadlineExceeded).
+            ///
+            /// Defaults to the retry time plus the timeout of the [`Config`](Config), counted
+            /// from when the request is first polled.
+            pub fn deadline(mut self, deadline: Instant) -> Self {
+                if let Some(options) = self.state.options_mut() {
+                    options.deadline = Some(deadline);
+                }
+                self
+            }
+        }
+    };
+}
+
+/// Implement the builder methods which set the [`RequestOptions`](RequestOptions) of a request.
+macro_rules! request_options {
+    ($type:ty) => {
+        deadline_option!($type);
+
+        impl $type {
+            /// Set the (optional) [`ColumnFamily`](ColumnFamily).
+            pub fn cf(mut self, cf: impl Into<ColumnFamily>) -> Self {
+                if let Some(options) = self.state.options_mut() {
+                    options.cf = Some(cf.into());
+                }
+                self
+            }
+
+            /// Set the timeout of the RPCs sent to TiKV for this request, overriding the timeout
+            /// of the [`Config`](Config).
+            pub fn timeout(mut self, timeout: Duration) -> Self {
+                if let Some(options) = self.state.options_mut() {
+                    options.timeout = Some(timeout);
+                }
+                self
+            }
+
+            /// Set the [`CommandPri`](CommandPri) TiKV schedules this request with.
+            pub fn priority(mut self, priority: CommandPri) -> Self {
+                if let Some(options) = self.state.options_mut() {
+                    options.priority = priority;
+                }
+                self
+            }
+
+            /// Don't fill TiKV's block cache with the data read by this request.
+            ///
+            /// This is useful for large reads which would otherwise evict hot data from the cache.
+            pub fn not_fill_cache(mut self) -> Self {
+                if let Some(options) = self.state.options_mut() {
+                    options.not_fill_cache = true;
+                }
+                self
+            }
+        }
+    };
+}
+
+/// Implement the builder methods which only apply to reads.
+macro_rules! read_options {
+    ($type:ty) => {
+        impl $type {
+            /// Set the [`ReplicaReadMode`](ReplicaReadMode) of this read, overriding the mode of
+            /// the [`Config`](Config).
+            pub fn replica_read(mut self, mode: ReplicaReadMode) -> Self {
+                if let Some(options) = self.state.options_mut() {
+                    options.replica_read = Some(mode);
+                }
+                self
+            }
+        }
+    };
+}
+
+request_options!(Get);
+request_options!(BatchGet);
+request_options!(BatchGetPartial);
+request_options!(BatchGetOrdered);
+request_options!(Put);
+request_options!(BatchPut);
+request_options!(BatchPutPartial);
+request_options!(Delete);
+request_options!(BatchDelete);
+request_options!(BatchDeletePartial);
+request_options!(Scan);
+request_options!(BatchScan);
+request_options!(DeleteRange);
+
+read_options!(Get);
+read_options!(BatchGet);
+read_options!(BatchGetPartial);
+read_options!(BatchGetOrdered);
+read_options!(Scan);
+read_options!(BatchScan);
+
+deadline_option!(PrefetchRegions);
+deadline_option!(SplitRange);
+deadline_option!(SplitRegions);
+deadline_option!(ScatterRegion);
+deadline_option!(WaitForScatter);
+deadline_option!(GetGcSafePoint);
+deadline_option!(UpdateGcSafePoint);
+deadline_option!(UpdateServiceGcSafePoint);
+deadline_option!(RemoveServiceGcSafePoint);
+
+/// An unresolved [`Client::get`](Client::get) request.
+///
+/// Once resolved this request will result in the fetching of the value associated with the given
+/// key.
+pub struct Get {
+    state: RequestState<GetInner>,
+}
+
+impl Get {
+    fn new(client: Arc<RpcClient>, inner: GetInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for Get {
+    type Output = Result<Option<Value>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct GetInner {
+    key: Key,
+}
+
+impl GetInner {
+    fn new(key: Key) -> Self {
+        GetInner { key }
+    }
+}
+
+impl RequestInner for GetInner {
+    type Resp = Option<Value>;
+
+    fn execute(
+        self,
+        client: Arc<RpcClient>,
+        options: RequestOptions,
+    ) -> BoxTryFuture<Option<Value>> {
+        Box::new(client.raw_get(self.key, options))
+    }
+}
+
+/// An unresolved [`Client::batch_get`](Client::batch_get) request.
+///
+/// Once resolved this request will result in the fetching of the values associated with the given
+/// keys.
+pub struct BatchGet {
+    state: RequestState<BatchGetInner>,
+}
+
+impl BatchGet {
+    fn new(client: Arc<RpcClient>, inner: BatchGetInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchGet {
+    type Output = Result<Vec<KvPair>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchGetInner {
+    keys: Vec<Key>,
+    prefix: Key,
+}
+
+impl RequestInner for BatchGetInner {
+    type Resp = Vec<KvPair>;
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<Vec<KvPair>> {
+        let prefix = self.prefix;
+        Box::new(
+            client
+                .raw_batch_get(self.keys, options)
+                .map_ok(move |pairs| strip_prefix(&prefix, pairs)),
+        )
+    }
+}
+
+impl BatchGetInner {
+    fn new(keys: Vec<Key>, prefix: Key) -> Self {
+        BatchGetInner { keys, prefix }
+    }
+}
+
+/// An unresolved [`Client::batch_get_partial`](Client::batch_get_partial) request.
+///
+/// Once resolved this request will result in a [`BatchResult`](BatchResult) of the batch.
+pub struct BatchGetPartial {
+    state: RequestState<BatchGetPartialInner>,
+}
+
+impl BatchGetPartial {
+    fn new(client: Arc<RpcClient>, inner: BatchGetPartialInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchGetPartial {
+    type Output = Result<BatchResult<KvPair>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchGetPartialInner {
+    keys: Vec<Key>,
+    prefix: Key,
+}
+
+impl BatchGetPartialInner {
+    fn new(keys: Vec<Key>, prefix: Key) -> Self {
+        BatchGetPartialInner { keys, prefix }
+    }
+}
+
+impl RequestInner for BatchGetPartialInner {
+    type Resp = BatchResult<KvPair>;
+
+    fn execute(
+        self,
+        client: Arc<RpcClient>,
+        options: RequestOptions,
+    ) -> BoxTryFuture<BatchResult<KvPair>> {
+        let prefix = self.prefix;
+        Box::new(
+            client
+                .raw_batch_get_partial(self.keys, options)
+                .map_ok(move |result| strip_batch_result_prefix(&prefix, result, KvPair::key_mut)),
+        )
+    }
+}
+
+/// An unresolved [`Client::batch_get_ordered`](Client::batch_get_ordered) request.
+///
+/// Once resolved this request will result in the fetching of the values associated with the given
+/// keys, in the order of the keys.
+pub struct BatchGetOrdered {
+    state: RequestState<BatchGetOrderedInner>,
+}
+
+impl BatchGetOrdered {
+    fn new(client: Arc<RpcClient>, inner: BatchGetOrderedInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchGetOrdered {
+    type Output = Result<Vec<Option<Value>>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchGetOrderedInner {
+    keys: Vec<Key>,
+}
+
+impl RequestInner for BatchGetOrderedInner {
+    type Resp = Vec<Option<Value>>;
+
+    fn execute(
+        self,
+        client: Arc<RpcClient>,
+        options: RequestOptions,
+    ) -> BoxTryFuture<Vec<Option<Value>>> {
+        Box::new(client.raw_batch_get_ordered(self.keys, options))
+    }
+}
+
+impl BatchGetOrderedInner {
+    fn new(keys: Vec<Key>) -> Self {
+        BatchGetOrderedInner { keys }
+    }
+}
+
+/// An unresolved [`Client::put`](Client::put) request.
+///
+/// Once resolved this request will result in the putting of the value associated with the given
+/// key.
+pub struct Put {
+    state: RequestState<PutInner>,
+}
+
+impl Put {
+    fn new(client: Arc<RpcClient>, inner: PutInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for Put {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct PutInner {
+    key: Key,
+    value: Value,
+}
+
+impl PutInner {
+    fn new(key: Key, value: Value) -> Self {
+        PutInner { key, value }
+    }
+}
+
+impl RequestInner for PutInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<()> {
+        let (key, value) = (self.key, self.value);
+        Box::new(client.raw_put(key, value, options))
+    }
+}
+
+/// An unresolved [`Client::batch_put`](Client::batch_put) request.
+///
+/// Once resolved this request will result in the setting of the value associated with the given key.
+pub struct BatchPut {
+    state: RequestState<BatchPutInner>,
+}
+
+impl BatchPut {
+    fn new(client: Arc<RpcClient>, inner: BatchPutInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchPut {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchPutInner {
+    pairs: Vec<KvPair>,
+}
+
+impl BatchPutInner {
+    fn new(pairs: Vec<KvPair>) -> Self {
+        BatchPutInner { pairs }
+    }
+}
+
+impl RequestInner for BatchPutInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<()> {
+        Box::new(client.raw_batch_put(self.pairs, options))
+    }
+}
+
+/// An unresolved [`Client::batch_put_partial`](Client::batch_put_partial) request.
+///
+/// Once resolved this request will result in a [`BatchResult`](BatchResult) of the batch.
+pub struct BatchPutPartial {
+    state: RequestState<BatchPutPartialInner>,
+}
+
+impl BatchPutPartial {
+    fn new(client: Arc<RpcClient>, inner: BatchPutPartialInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchPutPartial {
+    type Output = Result<BatchResult<Key>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchPutPartialInner {
+    pairs: Vec<KvPair>,
+    prefix: Key,
+}
+
+impl BatchPutPartialInner {
+    fn new(pairs: Vec<KvPair>, prefix: Key) -> Self {
+        BatchPutPartialInner { pairs, prefix }
+    }
+}
+
+impl RequestInner for BatchPutPartialInner {
+    type Resp = BatchResult<Key>;
+
+    fn execute(
+        self,
+        client: Arc<RpcClient>,
+        options: RequestOptions,
+    ) -> BoxTryFuture<BatchResult<Key>> {
+        let prefix = self.prefix;
+        Box::new(
+            client
+                .raw_batch_put_partial(self.pairs, options)
+                .map_ok(move |result| strip_batch_result_prefix(&prefix, result, |key| key)),
+        )
+    }
+}
+
+/// An unresolved [`Client::delete`](Client::delete) request.
+///
+/// Once resolved this request will result in the deletion of the given key.
+pub struct Delete {
+    state: RequestState<DeleteInner>,
+}
+
+impl Delete {
+    fn new(client: Arc<RpcClient>, inner: DeleteInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for Delete {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct DeleteInner {
+    key: Key,
+}
+
+impl DeleteInner {
+    fn new(key: Key) -> Self {
+        DeleteInner { key }
+    }
+}
+
+impl RequestInner for DeleteInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<()> {
+        Box::new(client.raw_delete(self.key, options))
+    }
+}
+
+/// An unresolved [`Client::batch_delete`](Client::batch_delete) request.
+///
+/// Once resolved this request will result in the deletion of the given keys.
+pub struct BatchDelete {
+    state: RequestState<BatchDeleteInner>,
+}
+
+impl BatchDelete {
+    fn new(client: Arc<RpcClient>, inner: BatchDeleteInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchDelete {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchDeleteInner {
+    keys: Vec<Key>,
+}
+
+impl BatchDeleteInner {
+    fn new(keys: Vec<Key>) -> Self {
+        BatchDeleteInner { keys }
+    }
+}
+
+impl RequestInner for BatchDeleteInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<()> {
+        Box::new(client.raw_batch_delete(self.keys, options))
+    }
+}
+
+/// An unresolved [`Client::batch_delete_partial`](Client::batch_delete_partial) request.
+///
+/// Once resolved this request will result in a [`BatchResult`](BatchResult) of the batch.
+pub struct BatchDeletePartial {
+    state: RequestState<BatchDeletePartialInner>,
+}
+
+impl BatchDeletePartial {
+    fn new(client: Arc<RpcClient>, inner: BatchDeletePartialInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for BatchDeletePartial {
+    type Output = Result<BatchResult<Key>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchDeletePartialInner {
+    keys: Vec<Key>,
+    prefix: Key,
+}
+
+impl BatchDeletePartialInner {
+    fn new(keys: Vec<Key>, prefix: Key) -> Self {
+        BatchDeletePartialInner { keys, prefix }
+    }
+}
+
+impl RequestInner for BatchDeletePartialInner {
+    type Resp = BatchResult<Key>;
+
+    fn execute(
+        self,
+        client: Arc<RpcClient>,
+        options: RequestOptions,
+    ) -> BoxTryFuture<BatchResult<Key>> {
+        let prefix = self.prefix;
+        Box::new(
+            client
+                .raw_batch_delete_partial(self.keys, options)
+                .map_ok(move |result| strip_batch_result_prefix(&prefix, result, |key| key)),
+        )
+    }
+}
+
+pub(crate) struct ScanInner {
+    range: (Bound<Key>, Bound<Key>),
+    limit: u32,
+    key_only: bool,
+    prefix: Key,
+}
+
+impl ScanInner {
+    fn new(range: (Bound<Key>, Bound<Key>), limit: u32, prefix: Key) -> Self {
+        ScanInner {
+            range,
+            limit,
+            key_only: false,
+            prefix,
+        }
+    }
+}
+
+impl RequestInner for ScanInner {
+    type Resp = Vec<KvPair>;
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<Vec<KvPair>> {
+        if self.limit > MAX_RAW_KV_SCAN_LIMIT {
+            Box::new(future::err(Error::max_scan_limit_exceeded(
+                self.limit,
+                MAX_RAW_KV_SCAN_LIMIT,
+            )))
+        } else {
+            let keys = match self.range.into_keys() {
+                Err(e) => return Box::new(future::err(e)),
+                Ok(v) => v,
+            };
+            let prefix = self.prefix;
+            Box::new(
+                client
+                    .raw_scan(keys, self.limit, self.key_only, options)
+                    .map_ok(move |pairs| strip_prefix(&prefix, pairs)),
+            )
+        }
+    }
+}
+
+/// An unresolved [`Client::scan`](Client::scan) request.
+///
+/// Once resolved this request will result in a scanner over the given range.
+pub struct Scan {
+    state: RequestState<ScanInner>,
+}
+
+impl Scan {
+    fn new(client: Arc<RpcClient>, inner: ScanInner) -> Self {
+        Scan {
+            state: RequestState::new(client, inner),
+        }
+    }
+
+    pub fn key_only(mut self) -> Self {
+        if let Some(x) = self.state.inner_mut() {
+            x.key_only = true;
+        };
+        self
+    }
+}
+
+impl Future for Scan {
+    type Output = Result<Vec<KvPair>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct BatchScanInner {
+    ranges: Vec<Result<(Key, Option<Key>)>>,
+    each_limit: u32,
+    key_only: bool,
+    prefix: Key,
+}
+
+impl BatchScanInner {
+    fn new(ranges: Vec<Result<(Key, Option<Key>)>>, each_limit: u32, prefix: Key) -> Self {
+        BatchScanInner {
+            ranges,
+            each_limit,
+            key_only: false,
+            prefix,
+        }
+    }
+}
+
+impl RequestInner for BatchScanInner {
+    type Resp = Vec<KvPair>;
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<Vec<KvPair>> {
+        if self.each_limit > MAX_RAW_KV_SCAN_LIMIT {
+            Box::new(future::err(Error::max_scan_limit_exceeded(
+                self.each_limit,
+                MAX_RAW_KV_SCAN_LIMIT,
+            )))
+        } else if self.ranges.iter().any(Result::is_err) {
+            // All errors must be InvalidKeyRange so we can simply return a new InvalidKeyRange
+            Box::new(future::err(Error::invalid_key_range()))
+        } else {
+            let prefix = self.prefix;
+            Box::new(
+                client
+                    .raw_batch_scan(
+                        self.ranges.into_iter().map(Result::unwrap).collect(),
+                        self.each_limit,
+                        self.key_only,
+                        options,
+                    )
+                    .map_ok(move |pairs| strip_prefix(&prefix, pairs)),
+            )
+        }
+    }
+}
+
+/// An unresolved [`Client::batch_scan`](Client::batch_scan) request.
+///
+/// Once resolved this request will result in a scanner over the given ranges.
+pub struct BatchScan {
+    state: RequestState<BatchScanInner>,
+}
+
+impl BatchScan {
+    fn new(client: Arc<RpcClient>, inner: BatchScanInner) -> Self {
+        BatchScan {
+            state: RequestState::new(client, inner),
+        }
+    }
+
+    pub fn key_only(mut self) -> Self {
+        if let Some(x) = self.state.inner_mut() {
+            x.key_only = true;
+        };
+        self
+    }
+}
+
+impl Future for BatchScan {
+    type Output = Result<Vec<KvPair>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+/// An unresolved [`Client::delete_range`](Client::delete_range) request.
+///
+/// Once resolved this request will result in the deletion of the values in the given
+/// range, and the number of regions which were cleared.
+pub struct DeleteRange {
+    state: RequestState<DeleteRangeInner>,
+}
+
+impl DeleteRange {
+    fn new(client: Arc<RpcClient>, inner: DeleteRangeInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for DeleteRange {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct DeleteRangeInner {
+    range: Result<(Key, Option<Key>)>,
+}
+
+impl DeleteRangeInner {
+    fn new(range: Result<(Key, Option<Key>)>) -> Self {
+        DeleteRangeInner { range }
+    }
+}
+
+impl RequestInner for DeleteRangeInner {
+    type Resp = usize;
+
+    fn execute(self, client: Arc<RpcClient>, options: RequestOptions) -> BoxTryFuture<usize> {
+        match self.range {
+            Ok(range) => Box::new(client.raw_delete_range(range, options)),
+            Err(e) => Box::new(future::err(e)),
+        }
+    }
+}
+
+/// An unresolved [`Client::prefetch_regions`](Client::prefetch_regions) request.
+///
+/// Once resolved this request will result in the number of regions overlapping the given range,
+/// which are now cached by the client.
+pub struct PrefetchRegions {
+    state: RequestState<PrefetchRegionsInner>,
+}
+
+impl PrefetchRegions {
+    fn new(client: Arc<RpcClient>, inner: PrefetchRegionsInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for PrefetchRegions {
+    type Output = Result<usize>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct PrefetchRegionsInner {
+    range: Result<(Key, Option<Key>)>,
+}
+
+impl PrefetchRegionsInner {
+    fn new(range: Result<(Key, Option<Key>)>) -> Self {
+        PrefetchRegionsInner { range }
+    }
+}
+
+impl RequestInner for PrefetchRegionsInner {
+    type Resp = usize;
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<usize> {
+        match self.range {
+            Ok(range) => Box::new(client.prefetch_regions(range)),
+            Err(e) => Box::new(future::err(e)),
+        }
+    }
+}
+
+/// An unresolved [`Client::split_range`](Client::split_range) request.
+///
+/// Once resolved this request will result in contiguous sub-ranges of the given range, aligned to
+/// region boundaries.
+pub struct SplitRange {
+    state: RequestState<SplitRangeInner>,
+}
+
+impl SplitRange {
+    fn new(client: Arc<RpcClient>, inner: SplitRangeInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for SplitRange {
+    type Output = Result<Vec<(Key, Option<Key>)>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct SplitRangeInner {
+    range: Result<(Key, Option<Key>)>,
+    max_chunks: usize,
+    prefix: Key,
+}
+
+impl SplitRangeInner {
+    fn new(range: Result<(Key, Option<Key>)>, max_chunks: usize, prefix: Key) -> Self {
+        SplitRangeInner {
+            range,
+            max_chunks,
+            prefix,
+        }
+    }
+}
+
+impl RequestInner for SplitRangeInner {
+    type Resp = Vec<(Key, Option<Key>)>;
+
+    fn execute(
+        self,
+        client: Arc<RpcClient>,
+        _options: RequestOptions,
+    ) -> BoxTryFuture<Vec<(Key, Option<Key>)>> {
+        match self.range {
+            Ok(range) => {
+                let prefix = self.prefix;
+                Box::new(
+                    client
+                        .split_range(range, self.max_chunks)
+                        .map_ok(move |ranges| strip_range_prefix(&prefix, ranges)),
+                )
+            }
+            Err(e) => Box::new(future::err(e)),
+        }
+    }
+}
+
+/// An unresolved [`Client::split_regions`](Client::split_regions) request.
+///
+/// Once resolved this request will result in the ids of the regions created by the split.
+pub struct SplitRegions {
+    state: RequestState<SplitRegionsInner>,
+}
+
+impl SplitRegions {
+    fn new(client: Arc<RpcClient>, inner: SplitRegionsInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for SplitRegions {
+    type Output = Result<Vec<u64>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct SplitRegionsInner {
+    split_keys: Vec<Key>,
+}
+
+impl SplitRegionsInner {
+    fn new(split_keys: Vec<Key>) -> Self {
+        SplitRegionsInner { split_keys }
+    }
+}
+
+impl RequestInner for SplitRegionsInner {
+    type Resp = Vec<u64>;
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<Vec<u64>> {
+        Box::new(client.split_regions(self.split_keys))
+    }
+}
+
+/// An unresolved [`Client::scatter_region`](Client::scatter_region) request.
+///
+/// Once resolved PD has scheduled moving the region, which happens in the background.
+pub struct ScatterRegion {
+    state: RequestState<ScatterRegionInner>,
+}
+
+impl ScatterRegion {
+    fn new(client: Arc<RpcClient>, inner: ScatterRegionInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for ScatterRegion {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct ScatterRegionInner {
+    region_id: u64,
+}
+
+impl ScatterRegionInner {
+    fn new(region_id: u64) -> Self {
+        ScatterRegionInner { region_id }
+    }
+}
+
+impl RequestInner for ScatterRegionInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<()> {
+        Box::new(client.scatter_region(self.region_id))
+    }
+}
+
+/// An unresolved [`Client::wait_for_scatter`](Client::wait_for_scatter) request.
+///
+/// Once resolved PD is no longer scattering the region. The wait fails once its
+/// [`deadline`](WaitForScatter::deadline) passes, even if PD is still scattering the region.
+pub struct WaitForScatter {
+    state: RequestState<WaitForScatterInner>,
+}
+
+impl WaitForScatter {
+    fn new(client: Arc<RpcClient>, inner: WaitForScatterInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for WaitForScatter {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct WaitForScatterInner {
+    region_id: u64,
+}
+
+impl WaitForScatterInner {
+    fn new(region_id: u64) -> Self {
+        WaitForScatterInner { region_id }
+    }
+}
+
+impl RequestInner for WaitForScatterInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<()> {
+        Box::new(client.wait_for_scatter(self.region_id))
+    }
+}
+
+/// An unresolved [`Client::get_gc_safe_point`](Client::get_gc_safe_point) request.
+///
+/// Once resolved this request will result in the GC safe point.
+pub struct GetGcSafePoint {
+    state: RequestState<GetGcSafePointInner>,
+}
+
+impl GetGcSafePoint {
+    fn new(client: Arc<RpcClient>, inner: GetGcSafePointInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for GetGcSafePoint {
+    type Output = Result<u64>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct GetGcSafePointInner;
+
+impl GetGcSafePointInner {
+    fn new() -> Self {
+        GetGcSafePointInner
+    }
+}
+
+impl RequestInner for GetGcSafePointInner {
+    type Resp = u64;
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<u64> {
+        Box::new(client.get_gc_safe_point())
+    }
+}
+
+/// An unresolved [`Client::update_gc_safe_point`](Client::update_gc_safe_point) request.
+///
+/// Once resolved this request will result in the new GC safe point.
+pub struct UpdateGcSafePoint {
+    state: RequestState<UpdateGcSafePointInner>,
+}
+
+impl UpdateGcSafePoint {
+    fn new(client: Arc<RpcClient>, inner: UpdateGcSafePointInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for UpdateGcSafePoint {
+    type Output = Result<u64>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct UpdateGcSafePointInner {
+    safe_point: u64,
+}
+
+impl UpdateGcSafePointInner {
+    fn new(safe_point: u64) -> Self {
+        UpdateGcSafePointInner { safe_point }
+    }
+}
+
+impl RequestInner for UpdateGcSafePointInner {
+    type Resp = u64;
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<u64> {
+        Box::new(client.update_gc_safe_point(self.safe_point))
+    }
+}
+
+/// An unresolved [`Client::update_service_gc_safe_point`](Client::update_service_gc_safe_point)
+/// request.
+///
+/// Once resolved this request will result in the minimum safe point of all services.
+pub struct UpdateServiceGcSafePoint {
+    state: RequestState<UpdateServiceGcSafePointInner>,
+}
+
+impl UpdateServiceGcSafePoint {
+    fn new(client: Arc<RpcClient>, inner: UpdateServiceGcSafePointInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for UpdateServiceGcSafePoint {
+    type Output = Result<u64>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct UpdateServiceGcSafePointInner {
+    service_id: String,
+    ttl: Duration,
+    safe_point: u64,
+}
+
+impl UpdateServiceGcSafePointInner {
+    fn new(service_id: String, ttl: Duration, safe_point: u64) -> Self {
+        UpdateServiceGcSafePointInner {
+            service_id,
+            ttl,
+            safe_point,
+        }
+    }
+}
+
+impl RequestInner for UpdateServiceGcSafePointInner {
+    type Resp = u64;
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<u64> {
+        Box::new(client.update_service_gc_safe_point(self.service_id, self.ttl, self.safe_point))
+    }
+}
+
+/// An unresolved [`Client::remove_service_gc_safe_point`](Client::remove_service_gc_safe_point)
+/// request.
+///
+/// Once resolved the safe point of the service is removed.
+pub struct RemoveServiceGcSafePoint {
+    state: RequestState<RemoveServiceGcSafePointInner>,
+}
+
+impl RemoveServiceGcSafePoint {
+    fn new(client: Arc<RpcClient>, inner: RemoveServiceGcSafePointInner) -> Self {
+        Self {
+            state: RequestState::new(client, inner),
+        }
+    }
+}
+
+impl Future for RemoveServiceGcSafePoint {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        unsafe { Pin::new_unchecked(&mut Pin::get_unchecked_mut(self).state).poll(cx) }
+    }
+}
+
+pub(crate) struct RemoveServiceGcSafePointInner {
+    service_id: String,
+}
+
+impl RemoveServiceGcSafePointInner {
+    fn new(service_id: String) -> Self {
+        RemoveServiceGcSafePointInner { service_id }
+    }
+}
+
+impl RequestInner for RemoveServiceGcSafePointInner {
+    type Resp = ();
+
+    fn execute(self, client: Arc<RpcClient>, _options: RequestOptions) -> BoxTryFuture<()> {
+        Box::new(client.remove_service_gc_safe_point(self.service_id))
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use futures::future::{self, ready, Either, Future, TryFutureExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    marker::PhantomData,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::{
+    codec::{Codec, JsonCodec, KeyCodec},
+    raw::Client,
+    Key, Result,
+};
+
+/// A typed wrapper around a raw [`Client`](Client).
+///
+/// Keys are encoded with their [`KeyCodec`](crate::codec::KeyCodec) implementation, which
+/// preserves their ordering, and values are serialized with the [`Codec`](crate::codec::Codec)
+/// `C` (JSON by default). Values which can not be decoded result in an
+/// [`ErrorKind::Decode`](crate::ErrorKind::Decode) error.
+///
+/// ```rust,no_run
+/// # #![feature(async_await)]
+/// # use tikv_client::{Config, codec::BincodeCodec, raw::{Client, TypedClient}};
+/// # use futures::prelude::*;
+/// # use serde_derive::{Deserialize, Serialize};
+/// #[derive(Serialize, Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// # futures::executor::block_on(async {
+/// # let connecting_client = Client::new(Config::new(vec!["192.168.0.100", "192.168.0.101"]));
+/// # let connected_client = connecting_client.await.unwrap();
+/// let users: TypedClient<u64, User, BincodeCodec> =
+///     TypedClient::new(connected_client.with_prefix("users/"));
+/// users.put(&1, &User { name: "TiKV".to_owned() }).await.unwrap();
+/// let user: Option<User> = users.get(&1).await.unwrap();
+/// let first_users: Vec<(u64, User)> = users.scan(0..100, 10).await.unwrap();
+/// # });
+/// ```
+pub struct TypedClient<K, V, C = JsonCodec> {
+    client: Client,
+    _phantom: PhantomData<fn() -> (K, V, C)>,
+}
+
+impl<K, V, C> TypedClient<K, V, C>
+where
+    K: KeyCodec,
+    V: Serialize + DeserializeOwned,
+    C: Codec,
+{
+    /// Create a new [`TypedClient`](TypedClient) which issues its requests through the given
+    /// [`Client`](Client).
+    pub fn new(client: Client) -> Self {
+        TypedClient {
+            client,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Get a reference to the underlying raw [`Client`](Client).
+    pub fn raw(&self) -> &Client {
+        &self.client
+    }
+
+    /// Fetch and decode the value associated with the given key.
+    pub fn get(&self, key: &K) -> impl Future<Output = Result<Option<V>>> {
+        self.client
+            .get(key.encode_key())
+            .and_then(|value| ready(value.map(|value| C::decode(&value)).transpose()))
+    }
+
+    /// Encode and set the value associated with the given key.
+    pub fn put(&self, key: &K, value: &V) -> impl Future<Output = Result<()>> {
+        match C::encode(value) {
+            Ok(value) => Either::Left(self.client.put(key.encode_key(), value)),
+            Err(e) => Either::Right(future::err(e)),
+        }
+    }
+
+    /// Scan and decode up to `limit` pairs over the given range of keys.
+    pub fn scan(
+        &self,
+        range: impl RangeBounds<K>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<(K, V)>>> {
+        let range = (
+            encode_bound(range.start_bound()),
+            encode_bound(range.end_bound()),
+        );
+        self.client.scan(range, limit).and_then(|pairs| {
+            ready(
+                pairs
+                    .into_iter()
+                    .map(|pair| {
+                        let (key, value) = pair.into_inner();
+                        Ok((K::decode_key(&key)?, C::decode(&value)?))
+                    })
+                    .collect::<Result<Vec<_>>>(),
+            )
+        })
+    }
+}
+
+fn encode_bound<K: KeyCodec>(bound: Bound<&K>) -> Bound<Key> {
+    match bound {
+        Bound::Included(key) => Bound::Included(key.encode_key()),
+        Bound::Excluded(key) => Bound::Excluded(key.encode_key()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    cmp,
+    time::{Duration, Instant},
+};
+
+use futures::prelude::Future;
+use rand::{thread_rng, Rng};
+
+use crate::{rpc::Runtime, Error, ErrorKind, Result};
+
+/// The kinds of errors which are retried, each with its own backoff.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BackoffKind {
+    /// The cached region of a request is outdated. Retrying right away mostly succeeds since the
+    /// region was invalidated and is reloaded from PD, so the backoff starts small.
+    RegionMiss,
+    /// TiKV is overloaded.
+    ServerBusy,
+}
+
+impl BackoffKind {
+    /// The kind of backoff `e` calls for, or `None` if retrying would not help.
+    pub fn of(e: &Error) -> Option<BackoffKind> {
+        match e.kind() {
+            ErrorKind::ServerIsBusy { .. } => Some(BackoffKind::ServerBusy),
+            _ if e.is_region_error() => Some(BackoffKind::RegionMiss),
+            // The request was sent over a BatchCommands stream the store doesn't support, and is
+            // resent as a unary request.
+            _ if e.is_grpc_unimplemented() => Some(BackoffKind::RegionMiss),
+            _ => None,
+        }
+    }
+
+    /// The first delay, the maximum delay and the maximum number of attempts of the kind.
+    fn budget(self) -> (Duration, Duration, u32) {
+        match self {
+            BackoffKind::RegionMiss => (Duration::from_millis(2), Duration::from_millis(500), 20),
+            BackoffKind::ServerBusy => (Duration::from_millis(2000), Duration::from_secs(10), 10),
+        }
+    }
+}
+
+/// Decides whether and after how long a failed request is retried.
+///
+/// The delay grows exponentially with the number of attempts of each [`BackoffKind`], with "equal
+/// jitter": half of the delay is random, so clients failing at the same time don't retry in
+/// lockstep. Each kind gives up after a fixed number of attempts, and no retry is made once the
+/// deadline would be exceeded.
+#[derive(Clone, Debug)]
+pub struct Backoff {
+    deadline: Instant,
+    region_miss_attempts: u32,
+    server_busy_attempts: u32,
+    /// Whether a request was retried right away on the leader TiKV hinted at.
+    followed_leader_hint: bool,
+}
+
+impl Backoff {
+    /// Create a new `Backoff` which retries until `max_retry_time` from now.
+    pub fn new(max_retry_time: Duration) -> Self {
+        Backoff::until(Instant::now() + max_retry_time)
+    }
+
+    /// Create a new `Backoff` which retries until `deadline`.
+    pub fn until(deadline: Instant) -> Self {
+        Backoff {
+            deadline,
+            region_miss_attempts: 0,
+            server_busy_attempts: 0,
+            followed_leader_hint: false,
+        }
+    }
+
+    /// A `Backoff` which never retries.
+    pub fn no_retry() -> Self {
+        Backoff::new(Duration::from_secs(0))
+    }
+
+    /// The delay before retrying a request which failed with `e`, or `None` if it should not be
+    /// retried.
+    pub fn next_delay(&mut self, e: &Error) -> Option<Duration> {
+        let kind = BackoffKind::of(e)?;
+        let (base, cap, max_attempts) = kind.budget();
+        let attempts = match kind {
+            BackoffKind::RegionMiss => &mut self.region_miss_attempts,
+            BackoffKind::ServerBusy => &mut self.server_busy_attempts,
+        };
+        if *attempts >= max_attempts {
+            return None;
+        }
+        *attempts += 1;
+
+        let delay = match e.kind() {
+            // The cached region was switched to the new leader, so the retry is likely to succeed.
+            // Only the first hop is immediate, in case TiKV keeps hinting at stale leaders.
+            ErrorKind::NotLeader {
+                leader: Some(_), ..
+            } if !self.followed_leader_hint => {
+                self.followed_leader_hint = true;
+                Duration::from_secs(0)
+            }
+            // The unary request doesn't depend on the failed stream.
+            _ if e.is_grpc_unimplemented() => Duration::from_secs(0),
+            // TiKV knows best how long it is going to be busy.
+            ErrorKind::ServerIsBusy { backoff_ms, .. } if *backoff_ms > 0 => {
+                Duration::from_millis(*backoff_ms)
+            }
+            _ => {
+                let delay = cmp::min(base * (1 << cmp::min(*attempts - 1, 16)), cap);
+                let half = delay / 2;
+                let jitter = thread_rng().gen_range(0, half.as_micros() as u64 + 1);
+                half + Duration::from_micros(jitter)
+            }
+        };
+        if Instant::now() + delay > self.deadline {
+            return None;
+        }
+        Some(delay)
+    }
+}
+
+/// A future which resolves after `delay`, timed by `runtime`.
+pub fn sleep(runtime: &dyn Runtime, delay: Duration) -> impl Future<Output = Result<()>> {
+    runtime.delay(Instant::now() + delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let region_miss = Error::stale_epoch(None);
+        let mut backoff = Backoff::new(Duration::from_secs(3600));
+        let mut previous_cap = Duration::from_millis(0);
+        for attempt in 0..20 {
+            let delay = backoff.next_delay(&region_miss).unwrap();
+            let cap = cmp::min(
+                Duration::from_millis(2 << attempt),
+                Duration::from_millis(500),
+            );
+            assert!(delay <= cap);
+            assert!(delay >= cap / 2);
+            assert!(cap >= previous_cap);
+            previous_cap = cap;
+        }
+        assert!(backoff.next_delay(&region_miss).is_none());
+
+        // Other kinds have their own budget.
+        let mut busy = kvproto::errorpb::ServerIsBusy::default();
+        busy.set_backoff_ms(42);
+        let busy = Error::server_is_busy(busy);
+        assert_eq!(backoff.next_delay(&busy), Some(Duration::from_millis(42)));
+
+        assert!(backoff.next_delay(&Error::empty_value()).is_none());
+
+        let mut backoff = Backoff::new(Duration::from_secs(3600));
+        let not_leader = Error::not_leader(1, Some(Default::default()), None);
+        assert_eq!(
+            backoff.next_delay(&not_leader),
+            Some(Duration::from_secs(0))
+        );
+        assert!(backoff.next_delay(&not_leader).unwrap() > Duration::from_secs(0));
+
+        let unimplemented = Error::from(grpcio::Error::RpcFailure(grpcio::RpcStatus::new(
+            grpcio::RpcStatusCode::Unimplemented,
+            None,
+        )));
+        assert_eq!(
+            backoff.next_delay(&unimplemented),
+            Some(Duration::from_secs(0))
+        );
+    }
+
+    #[test]
+    fn test_backoff_deadline() {
+        let mut backoff = Backoff::no_retry();
+        assert!(backoff.next_delay(&Error::stale_epoch(None)).is_none());
+    }
+}
@@ -4,39 +4,122 @@
 #![allow(dead_code)]
 
 use std::{
+    cmp,
     collections::hash_map::{self, HashMap},
     fmt,
-    sync::{Arc, RwLock},
-    time::Duration,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+    time::{Duration, Instant},
 };
 
 use futures::future::{self, ready, Either, Future};
-use futures::prelude::TryFutureExt;
+use futures::prelude::{FutureExt, StreamExt, TryFutureExt};
+use futures::stream;
 use grpcio::{EnvBuilder, Environment};
-use kvproto::kvrpcpb;
+use kvproto::{kvrpcpb, metapb, pdpb};
 use log::*;
 
 use crate::{
     compat::{loop_fn, Loop},
-    raw::ColumnFamily,
+    raw::{BatchResult, RequestOptions},
     rpc::{
-        pd::{PdClient, PdTimestamp, Region, RegionId, RegionVerId, Store, StoreId},
+        backoff::{sleep, Backoff},
+        limiter::{Limiter, Permit},
+        pd::{
+            PdClient, PdTimestamp, Peer, Region, RegionId, RegionVerId, Store, StoreId, TsoOptions,
+        },
+        pool::ConnectionPool,
+        region_cache::RegionCache,
         security::SecurityManager,
         tikv::KvClient,
         util::HandyRwLock,
+        DefaultRuntime, Runtime,
     },
-    Config, Error, Key, KvPair, Result, Value,
+    Config, Error, ErrorKind, Key, KvPair, ReplicaReadMode, Result, Value,
 };
 
 const CQ_COUNT: usize = 1;
 const CLIENT_PREFIX: &str = "tikv-client";
+/// The maximum number of regions loaded from PD in a single round trip.
+const SCAN_REGIONS_LIMIT: i32 = 128;
+/// How often the stores are fetched from PD to clean up removed stores and idle connections.
+const STORE_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+/// How many times PD retries a failed region split.
+const SPLIT_REGIONS_RETRY_LIMIT: u64 = 16;
+/// The description of the operators PD schedules to scatter a region.
+const SCATTER_REGION_OPERATOR: &[u8] = b"scatter-region";
+/// How often PD is asked whether a region is still being scattered.
+const SCATTER_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+type BoxTryFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+
+/// How raw batch writes are split into RPCs, see [`Config`](Config).
+#[derive(Clone, Copy, Debug)]
+struct BatchLimits {
+    max_keys: usize,
+    max_bytes: usize,
+    concurrency: usize,
+}
+
+impl BatchLimits {
+    fn new(config: &Config) -> Self {
+        BatchLimits {
+            max_keys: config.raw_batch_max_keys,
+            max_bytes: config.raw_batch_max_bytes,
+            concurrency: cmp::max(config.raw_batch_concurrency, 1),
+        }
+    }
+
+    /// Split `tasks` into batches of at most `max_keys` tasks and `max_bytes` bytes. A task which
+    /// is larger than `max_bytes` on its own is sent in a batch by itself.
+    fn split<Task: GroupingTask>(&self, tasks: Vec<Task>) -> Vec<Vec<Task>> {
+        let mut batches = Vec::new();
+        let mut batch = Vec::new();
+        let mut batch_bytes = 0;
+        for task in tasks {
+            let task_bytes = task.size();
+            if !batch.is_empty()
+                && ((self.max_keys > 0 && batch.len() >= self.max_keys)
+                    || (self.max_bytes > 0 && batch_bytes + task_bytes > self.max_bytes))
+            {
+                batches.push(batch);
+                batch = Vec::new();
+                batch_bytes = 0;
+            }
+            batch_bytes += task_bytes;
+            batch.push(task);
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+}
 
 struct RpcClientInner {
     pd: Arc<PdClient>,
-    tikv: Arc<RwLock<HashMap<String, Arc<KvClient>>>>,
+    tikv: Arc<RwLock<ConnectionPool<KvClient>>>,
+    /// Limits the requests in flight to all stores, if there is a limit.
+    limiter: Option<Arc<Limiter>>,
+    /// Limits the requests in flight to each store, created as the stores are used.
+    store_limiters: Mutex<HashMap<StoreId, Arc<Limiter>>>,
+    max_in_flight_per_store: usize,
+    queue_timeout: Duration,
+    region_cache: Arc<RwLock<RegionCache>>,
     env: Arc<Environment>,
     security_mgr: Arc<SecurityManager>,
     timeout: Duration,
+    batch_limits: BatchLimits,
+    replica_read: ReplicaReadMode,
+    max_retry_time: Duration,
+    runtime: Arc<dyn Runtime>,
+    /// How long raw requests wait to be coalesced, if they are sent over BatchCommands streams.
+    batch_commands_wait: Option<Duration>,
+    /// Rotates the peers replica reads are sent to.
+    replica_read_seed: AtomicUsize,
 }
 
 impl RpcClientInner {
@@ -57,22 +140,96 @@ impl RpcClientInner {
             },
         );
 
+        let runtime: Arc<dyn Runtime> = match &config.runtime {
+            Some(runtime) => Arc::clone(&runtime.0),
+            None => Arc::new(DefaultRuntime::new()?),
+        };
+
         let pd = Arc::new(PdClient::connect(
             Arc::clone(&env),
             &config.pd_endpoints,
             Arc::clone(&security_mgr),
             config.timeout,
+            Arc::clone(&runtime),
+            TsoOptions {
+                max_wait: config.tso_batch_wait,
+                max_batch_size: config.tso_batch_max_size,
+                prefetch: config.tso_prefetch,
+            },
         )?);
-        let tikv = Default::default();
+        let tikv = Arc::new(RwLock::new(ConnectionPool::new(
+            config.connections_per_store,
+            config.connection_idle_timeout,
+        )));
         Ok(RpcClientInner {
             pd,
             tikv,
+            limiter: if config.max_in_flight > 0 {
+                Some(Arc::new(Limiter::new(
+                    config.max_in_flight,
+                    "the cluster",
+                    Arc::clone(&runtime),
+                )))
+            } else {
+                None
+            },
+            store_limiters: Default::default(),
+            max_in_flight_per_store: config.max_in_flight_per_store,
+            queue_timeout: config.queue_timeout,
+            region_cache: Default::default(),
             env,
             security_mgr,
             timeout: config.timeout,
+            batch_limits: BatchLimits::new(config),
+            replica_read: config.replica_read,
+            max_retry_time: config.max_retry_time,
+            runtime,
+            batch_commands_wait: if config.batch_commands {
+                Some(config.batch_commands_wait)
+            } else {
+                None
+            },
+            replica_read_seed: AtomicUsize::new(0),
+        })
+    }
+
+    fn runtime(&self) -> Arc<dyn Runtime> {
+        Arc::clone(&self.runtime)
+    }
+
+    /// The [`Backoff`](Backoff) of a new request with the given options. No retry is made past the
+    /// deadline of the request.
+    fn backoff(&self, options: &RequestOptions) -> Backoff {
+        let deadline = Instant::now() + self.max_retry_time;
+        Backoff::until(match options.deadline {
+            Some(request_deadline) => cmp::min(deadline, request_deadline),
+            None => deadline,
         })
     }
 
+    /// The [`ReplicaReadMode`](ReplicaReadMode) of a read with the given options.
+    fn read_mode(&self, options: &RequestOptions) -> ReplicaReadMode {
+        options.replica_read.unwrap_or(self.replica_read)
+    }
+
+    /// Pick the peer of `region` to send a request to in the given mode.
+    fn select_peer(&self, region: &Region, mode: ReplicaReadMode) -> Result<Peer> {
+        let peers: Vec<&Peer> = match mode {
+            ReplicaReadMode::Leader => return region.peer(),
+            ReplicaReadMode::Follower => region.followers(),
+            ReplicaReadMode::LeaderAndFollower => region
+                .leader
+                .iter()
+                .chain(region.followers().into_iter())
+                .collect(),
+        };
+        if peers.is_empty() {
+            return region.peer();
+        }
+        let index = self.replica_read_seed.fetch_add(1, Ordering::Relaxed) % peers.len();
+        Ok(peers[index].clone())
+    }
+
     fn get_all_stores(&self) -> impl Future<Output = Result<Vec<Store>>> {
         self.pd.get_all_stores()
     }
@@ -106,29 +263,288 @@ impl RpcClientInner {
         self.pd.get_region_by_id(id)
     }
 
+    /// Load the consecutive regions from the one containing `start_key` to the last one before
+    /// `end_key` (but at most [`SCAN_REGIONS_LIMIT`](SCAN_REGIONS_LIMIT) of them) from PD in a
+    /// single round trip, and cache them.
+    fn load_regions(
+        &self,
+        start_key: &Key,
+        end_key: Option<&Key>,
+    ) -> impl Future<Output = Result<Vec<Region>>> {
+        let cache = Arc::clone(&self.region_cache);
+        let end_key = end_key.map(|key| key.to_vec()).unwrap_or_default();
+        self.pd
+            .scan_regions(start_key.as_ref(), &end_key, SCAN_REGIONS_LIMIT)
+            .map_ok(move |regions| {
+                let mut cache = cache.wl();
+                for region in &regions {
+                    cache.add_region(region.clone());
+                }
+                regions
+            })
+    }
+
+    /// Like [`locate_key`](RpcClientInner::locate_key), but if the region of `key` is not cached,
+    /// the regions following it up to `end_key` are loaded along with it, so that requests
+    /// visiting them don't need to ask PD again.
+    fn locate_key_in_range(
+        &self,
+        key: &Key,
+        end_key: Option<&Key>,
+    ) -> impl Future<Output = Result<KeyLocation>> {
+        if let Some(region) = self.region_cache.rl().region_by_key(key) {
+            return Either::Left(future::ok(region));
+        }
+        let key = key.clone();
+        Either::Right(self.load_regions(&key, end_key).and_then(move |regions| {
+            ready(
+                regions
+                    .into_iter()
+                    .find(|region| region.contains(&key))
+                    .ok_or_else(|| Error::region_for_key_not_found(key.into_inner())),
+            )
+        }))
+    }
+
     fn locate_key(&self, key: &Key) -> impl Future<Output = Result<KeyLocation>> {
-        self.load_region(key)
+        if let Some(region) = self.region_cache.rl().region_by_key(key) {
+            return Either::Left(future::ok(region));
+        }
+        let cache = Arc::clone(&self.region_cache);
+        Either::Right(self.load_region(key).map_ok(move |region| {
+            cache.wl().add_region(region.clone());
+            region
+        }))
+    }
+
+    fn locate_region_by_id(&self, id: RegionId) -> impl Future<Output = Result<Region>> {
+        if let Some(region) = self.region_cache.rl().region_by_id(id) {
+            return Either::Left(future::ok(region));
+        }
+        let cache = Arc::clone(&self.region_cache);
+        Either::Right(self.load_region_by_id(id).map_ok(move |region| {
+            cache.wl().add_region(region.clone());
+            region
+        }))
+    }
+
+    fn locate_store(&self, id: StoreId) -> impl Future<Output = Result<Store>> {
+        if let Some(store) = self.region_cache.rl().store(id) {
+            return Either::Left(future::ok(store));
+        }
+        let cache = Arc::clone(&self.region_cache);
+        Either::Right(self.load_store(id).map_ok(move |store| {
+            cache.wl().add_store(store.clone());
+            store
+        }))
+    }
+
+    /// Invalidate the cached information a request to region `region_id` was sent with, if the
+    /// request failed because that information is outdated.
+    ///
+    /// If the request failed because the leader changed and TiKV told us the new leader, the
+    /// cached region is switched to the new leader instead, so the retry does not need to ask PD.
+    /// Such a hint is only followed once: if the request to the hinted leader fails as well, the
+    /// region is invalidated so it is reloaded from PD, and the error is turned into a region
+    /// error so that the request is retried.
+    fn on_request_error(&self, region_id: RegionId, store_id: StoreId, e: Error) -> Error {
+        match e.kind() {
+            ErrorKind::NotLeader {
+                leader: Some(_), ..
+            } if self.region_cache.rl().is_leader_hinted(region_id) => {
+                info!(
+                    "invalidate cached region {}, its hinted leader is stale: {}",
+                    region_id, e
+                );
+                self.region_cache.wl().invalidate_region(region_id);
+            }
+            ErrorKind::NotLeader {
+                leader: Some(leader),
+                ..
+            } => {
+                if self.region_cache.wl().switch_leader(region_id, leader) {
+                    info!(
+                        "switch cached region {} to leader on store {}",
+                        region_id,
+                        leader.get_store_id()
+                    );
+                } else {
+                    info!("invalidate cached region {}: {}", region_id, e);
+                }
+            }
+            ErrorKind::StoreNotMatch { .. } => {
+                info!("invalidate cached store {}: {}", store_id, e);
+                let mut cache = self.region_cache.wl();
+                cache.invalidate_region(region_id);
+                cache.invalidate_store(store_id);
+            }
+            // The command was proposed in a stale term, the cached region is still right.
+            ErrorKind::StaleCommand { .. } => {}
+            _ if e.is_region_error() => {
+                info!("invalidate cached region {}: {}", region_id, e);
+                self.region_cache.wl().invalidate_region(region_id);
+            }
+            ErrorKind::Grpc(_) if self.region_cache.rl().is_leader_hinted(region_id) => {
+                info!(
+                    "invalidate cached region {}, its hinted leader failed: {}",
+                    region_id, e
+                );
+                self.region_cache.wl().invalidate_region(region_id);
+                return Error::not_leader(region_id, None, Some(e.to_string()));
+            }
+            _ => {}
+        }
+        e
     }
 
     fn kv_client(&self, context: RegionContext) -> Result<(RegionContext, Arc<KvClient>)> {
         if let Some(conn) = self.tikv.rl().get(context.address()) {
-            return Ok((context, Arc::clone(conn)));
+            return Ok((context, conn));
         };
-        info!("connect to tikv endpoint: {:?}", context.address());
+        let index = self.tikv.rl().len(context.address());
+        info!(
+            "connect to tikv endpoint: {:?} ({})",
+            context.address(),
+            index
+        );
         let tikv = Arc::clone(&self.tikv);
         KvClient::connect(
             Arc::clone(&self.env),
             context.address(),
+            index,
             &self.security_mgr,
             self.timeout,
+            self.batch_commands_wait,
+            Arc::clone(&self.runtime),
         )
         .map(Arc::new)
         .map(|c| {
-            tikv.wl()
-                .insert(context.address().to_owned(), Arc::clone(&c));
+            let c = tikv.wl().add(context.address(), c);
             (context, c)
         })
     }
+
+    /// Wait until a request may be sent to `store_id` without exceeding the limits on requests in
+    /// flight. The request may be sent as long as the returned permits are held.
+    fn acquire(
+        &self,
+        store_id: StoreId,
+    ) -> impl Future<Output = Result<(Option<Permit>, Option<Permit>)>> {
+        let timeout = self.queue_timeout;
+        let store_permit = if self.max_in_flight_per_store > 0 {
+            let limiter = Arc::clone(
+                self.store_limiters
+                    .lock()
+                    .unwrap()
+                    .entry(store_id)
+                    .or_insert_with(|| {
+                        Arc::new(Limiter::new(
+                            self.max_in_flight_per_store,
+                            format!("store {}", store_id),
+                            Arc::clone(&self.runtime),
+                        ))
+                    }),
+            );
+            Either::Left(limiter.acquire(timeout).map_ok(Some))
+        } else {
+            Either::Right(future::ok(None))
+        };
+        // The store's permit is taken first, so that requests queueing for a busy store don't
+        // hold up the requests to other stores.
+        let limiter = self.limiter.clone();
+        store_permit.and_then(move |store_permit| match limiter {
+            Some(limiter) => Either::Left(
+                limiter
+                    .acquire(timeout)
+                    .map_ok(move |permit| (store_permit, Some(permit))),
+            ),
+            None => Either::Right(future::ok((store_permit, None))),
+        })
+    }
+
+    /// Evict the connection a request failed on, if the failure means the connection is broken.
+    fn on_connection_error(&self, client: &Arc<KvClient>, e: &Error) {
+        let broken = match e.kind() {
+            ErrorKind::Grpc(grpcio::Error::RpcFailure(status)) => {
+                status.status == grpcio::RpcStatusCode::Unavailable
+            }
+            ErrorKind::Grpc(grpcio::Error::RemoteStopped) => true,
+            _ => false,
+        };
+        if broken && self.tikv.wl().evict(client.address(), client) {
+            info!("evict connection to {}: {}", client.address(), e);
+        }
+    }
+
+    /// Refresh the stores every `STORE_REFRESH_INTERVAL` in the background, off the request
+    /// path. Stops once the client is dropped.
+    fn watch_stores(inner: &Arc<RpcClientInner>) {
+        let runtime = Arc::clone(&inner.runtime);
+        let watch =
+            loop_fn(Arc::downgrade(inner), {
+                let runtime = Arc::clone(&runtime);
+                move |inner: Weak<RpcClientInner>| {
+                    runtime
+                        .delay(Instant::now() + STORE_REFRESH_INTERVAL)
+                        .then(move |_| {
+                            let inner = match inner.upgrade() {
+                                Some(inner) => inner,
+                                None => return Either::Left(ready(Ok(Loop::Break(())))),
+                            };
+                            Either::Right(Self::refresh_stores(&inner).map(move |_| {
+                                Ok::<_, Error>(Loop::Continue(Arc::downgrade(&inner)))
+                            }))
+                        })
+                }
+            });
+        runtime.spawn(watch.map(|_| ()).boxed());
+    }
+
+    /// Fetch the stores from PD to clean up after the stores which are no longer up, or which
+    /// were removed from the cluster altogether: their connections are closed, and their cached
+    /// information and limiters dropped. Idle connections are closed as well.
+    ///
+    /// Failing to fetch the stores is not an error, they are fetched again later.
+    fn refresh_stores(inner: &Arc<RpcClientInner>) -> impl Future<Output = ()> {
+        let inner = Arc::clone(inner);
+        inner.pd.get_all_stores().map(move |stores| {
+            let stores = match stores {
+                Ok(stores) => stores,
+                Err(e) => {
+                    warn!("failed to refresh stores: {}", e);
+                    return;
+                }
+            };
+            let up: HashMap<StoreId, String> = stores
+                .into_iter()
+                .filter(|store| store.get_state() == metapb::StoreState::Up)
+                .map(|mut store| (store.get_id(), store.take_address()))
+                .collect();
+
+            let mut tikv = inner.tikv.wl();
+            for address in tikv.evict_idle() {
+                info!("close idle connections to {}", address);
+            }
+            for address in tikv.retain_stores(|address| up.values().any(|up| up == address)) {
+                info!(
+                    "close connections to store at {}, which is no longer up",
+                    address
+                );
+            }
+            for id in inner
+                .region_cache
+                .wl()
+                .retain_stores(|id| up.contains_key(&id))
+            {
+                info!("clean up store {}, which is no longer up", id);
+            }
+            inner
+                .store_limiters
+                .lock()
+                .unwrap()
+                .retain(|id, _| up.contains_key(id));
+        })
+    }
 }
 
 pub struct RpcClient {
@@ -137,28 +553,38 @@ pub struct RpcClient {
 
 impl RpcClient {
     pub fn connect(config: &Config) -> Result<RpcClient> {
-        Ok(RpcClient {
-            inner: Arc::new(RpcClientInner::connect(config)?),
-        })
+        let inner = Arc::new(RpcClientInner::connect(config)?);
+        RpcClientInner::watch_stores(&inner);
+        Ok(RpcClient { inner })
     }
 
+    /// Group `tasks` by the regions of their keys.
+    ///
+    /// The tasks are visited in key order, and regions which are not cached are loaded from PD
+    /// together with the regions following them, so a batch spanning many regions only needs a
+    /// few round trips to PD.
     fn group_tasks_by_region<Task>(
-        &self,
-        tasks: Vec<Task>,
+        inner: Arc<RpcClientInner>,
+        mut tasks: Vec<Task>,
     ) -> impl Future<Output = Result<GroupedTasks<Task>>>
     where
         Task: GroupingTask,
     {
+        tasks.sort_by(|a, b| a.key().cmp(b.key()));
+        // The regions up to the one containing the last key are needed.
+        let end_key = tasks.last().map(|task| {
+            let mut end_key = task.key().clone().into_inner();
+            end_key.push(0);
+            Key::from(end_key)
+        });
         let result: Option<GroupedTasks<Task>> = None;
-        let inner = self.inner();
         loop_fn((0, tasks, result), move |(mut index, tasks, mut result)| {
             if index == tasks.len() {
                 Either::Left(future::ok(Loop::Break(result)))
             } else {
-                let inner = Arc::clone(&inner);
                 Either::Right(
                     inner
-                        .locate_key(tasks[index].key())
+                        .locate_key_in_range(tasks[index].key(), end_key.as_ref())
                         .map_ok(move |location| {
                             while let Some(item) = tasks.get(index) {
                                 if !location.contains(item.key()) {
@@ -189,162 +615,655 @@ impl RpcClient {
         inner: Arc<RpcClientInner>,
         key: &Key,
     ) -> impl Future<Output = Result<(RegionContext, Arc<KvClient>)>> {
-        let inner2 = Arc::clone(&inner);
-        inner
-            .locate_key(key)
-            .and_then(move |location| {
-                let peer = location.peer().expect("leader must exist");
-                let store_id = peer.get_store_id();
-                inner.load_store(store_id).map_ok(|store| RegionContext {
-                    region: location,
-                    store,
-                })
-            })
-            .and_then(move |region| ready(inner2.kv_client(region)))
+        inner.locate_key(key).and_then(move |region| {
+            Self::region_context_with_mode(inner, region, ReplicaReadMode::Leader)
+        })
     }
 
     fn region_context_by_id(
         inner: Arc<RpcClientInner>,
         id: RegionId,
+        mode: ReplicaReadMode,
     ) -> impl Future<Output = Result<(RegionContext, Arc<KvClient>)>> {
-        let inner2 = Arc::clone(&inner);
         inner
-            .load_region_by_id(id)
-            .and_then(move |region| {
-                let peer = region.peer().expect("leader must exist");
-                let store_id = peer.get_store_id();
-                inner
-                    .load_store(store_id)
-                    .map_ok(|store| RegionContext { region, store })
-            })
-            .and_then(move |region| ready(inner2.kv_client(region)))
+            .locate_region_by_id(id)
+            .and_then(move |region| Self::region_context_with_mode(inner, region, mode))
     }
 
-    fn raw(
+    fn region_context_with_mode(
+        inner: Arc<RpcClientInner>,
+        region: Region,
+        mode: ReplicaReadMode,
+    ) -> impl Future<Output = Result<(RegionContext, Arc<KvClient>)>> {
+        let inner2 = Arc::clone(&inner);
+        ready(
+            inner
+                .select_peer(&region, mode)
+                .map(|peer| (inner, region, peer)),
+        )
+        .and_then(move |(inner, region, peer)| {
+            inner
+                .locate_store(peer.get_store_id())
+                .map_ok(move |store| RegionContext::new(region, peer, store))
+        })
+        .and_then(move |region| ready(inner2.kv_client(region)))
+    }
+
+    /// Send a raw read for `region`, to the peer picked by the
+    /// [`ReplicaReadMode`](ReplicaReadMode) of the request. If the read was sent to a follower and
+    /// failed, it is retried on the leader.
+    fn raw_read<Out, Exec, Fut>(
+        inner: Arc<RpcClientInner>,
+        region: Region,
+        options: RequestOptions,
+        exec: Exec,
+    ) -> impl Future<Output = Result<Out>>
+    where
+        Exec: Fn(RawContext) -> Fut + Clone,
+        Fut: Future<Output = Result<Out>>,
+    {
+        let mode = inner.read_mode(&options);
+        Self::region_context_with_mode(Arc::clone(&inner), region.clone(), mode).and_then(
+            move |(context, client)| {
+                let replica_read = context.is_replica_read();
+                let context = RawContext::new(context, client, options.clone());
+                Self::raw_send(Arc::clone(&inner), context, exec.clone()).or_else(move |e| {
+                    if replica_read {
+                        warn!("replica read failed, retrying on the leader: {}", e);
+                        let inner2 = Arc::clone(&inner);
+                        Either::Left(
+                            Self::region_context_with_mode(inner, region, ReplicaReadMode::Leader)
+                                .and_then(move |(context, client)| {
+                                    let context = RawContext::new(context, client, options);
+                                    Self::raw_send(inner2, context, exec)
+                                }),
+                        )
+                    } else {
+                        Either::Right(future::err(e))
+                    }
+                })
+            },
+        )
+    }
+
+    /// Send a raw request for `key` to the leader of its region.
+    fn raw<Out, Fut>(
         inner: Arc<RpcClientInner>,
         key: &Key,
-        cf: Option<ColumnFamily>,
-    ) -> impl Future<Output = Result<RawContext>> {
-        Self::region_context(inner, key)
-            .map_ok(|(region, client)| RawContext::new(region, client, cf))
+        options: RequestOptions,
+        exec: impl FnOnce(RawContext) -> Fut,
+    ) -> impl Future<Output = Result<Out>>
+    where
+        Fut: Future<Output = Result<Out>>,
+    {
+        let inner2 = Arc::clone(&inner);
+        Self::region_context(inner, key).and_then(move |(region, client)| {
+            Self::raw_send(inner2, RawContext::new(region, client, options), exec)
+        })
+    }
+
+    /// Send a raw request in `context` once the limits on requests in flight allow it,
+    /// invalidating the cached region (or store) the request was sent with if it fails because
+    /// they are outdated, and evicting the connection it was sent over if it broke.
+    fn raw_send<Out, Fut>(
+        inner: Arc<RpcClientInner>,
+        context: RawContext,
+        exec: impl FnOnce(RawContext) -> Fut,
+    ) -> impl Future<Output = Result<Out>>
+    where
+        Fut: Future<Output = Result<Out>>,
+    {
+        let region_id = context.region.region.id();
+        let store_id = context.region.store.get_id();
+        let client = context.client();
+        inner
+            .acquire(store_id)
+            .and_then(move |permits| {
+                exec(context).map(move |result| {
+                    drop(permits);
+                    result
+                })
+            })
+            .map_err(move |e| {
+                inner.on_connection_error(&client, &e);
+                inner.on_request_error(region_id, store_id, e)
+            })
     }
 
     fn txn(inner: Arc<RpcClientInner>, key: &Key) -> impl Future<Output = Result<TxnContext>> {
         Self::region_context(inner, key).map_ok(|(region, _client)| TxnContext::new(region))
     }
 
+    /// The [`Runtime`](Runtime) the client runs its background tasks and timers on.
+    pub fn runtime(&self) -> Arc<dyn Runtime> {
+        self.inner.runtime()
+    }
+
+    /// The deadline of a request which starts now and has no deadline of its own: it may be
+    /// retried for the `max_retry_time` of the [`Config`](Config), and then needs up to the
+    /// `timeout` of the `Config` for its last attempt.
+    pub fn default_deadline(&self) -> Instant {
+        Instant::now() + self.inner.max_retry_time + self.inner.timeout
+    }
+
     #[inline]
     fn inner(&self) -> Arc<RpcClientInner> {
         Arc::clone(&self.inner)
     }
 
+    /// Load the regions overlapping `range` from PD into the region cache, returning how many
+    /// there are.
+    pub fn prefetch_regions(
+        &self,
+        range: (Key, Option<Key>),
+    ) -> impl Future<Output = Result<usize>> {
+        self.regions(range).map_ok(|regions| regions.len())
+    }
+
+    /// The regions overlapping `range`, loaded from PD and cached.
+    pub fn regions(&self, range: (Key, Option<Key>)) -> impl Future<Output = Result<Vec<Region>>> {
+        let (start_key, end_key) = range;
+        if let Some(end_key) = &end_key {
+            if end_key < &start_key {
+                return Either::Left(future::err(Error::invalid_key_range()));
+            } else if end_key == &start_key {
+                return Either::Left(future::ok(Vec::new()));
+            }
+        }
+        let inner = self.inner();
+        Either::Right(loop_fn(
+            (start_key, Vec::new()),
+            move |(start_key, mut loaded): (Key, Vec<Region>)| {
+                let end_key = end_key.clone();
+                inner
+                    .load_regions(&start_key, end_key.as_ref())
+                    .map_ok(move |regions| {
+                        let next_key = match regions.last() {
+                            Some(region) => Key::from(region.end_key().to_vec()),
+                            None => return Loop::Break(loaded),
+                        };
+                        loaded.extend(regions);
+                        let done = next_key.is_empty()
+                            || end_key.map_or(false, |end_key| next_key >= end_key);
+                        if done {
+                            Loop::Break(loaded)
+                        } else {
+                            Loop::Continue((next_key, loaded))
+                        }
+                    })
+            },
+        ))
+    }
+
+    /// The region containing `key`, loaded from PD and cached.
+    pub fn region(&self, key: &Key) -> impl Future<Output = Result<Region>> {
+        let cache = Arc::clone(&self.inner.region_cache);
+        self.inner
+            .pd
+            .get_region(key.as_ref())
+            .map_ok(move |region| {
+                cache.wl().add_region(region.clone());
+                region
+            })
+    }
+
+    /// All stores of the cluster, including those which are offline or removed.
+    pub fn stores(&self) -> impl Future<Output = Result<Vec<Store>>> {
+        self.inner.pd.get_all_stores()
+    }
+
+    /// Split the regions containing `split_keys` at those keys, and return the ids of the newly
+    /// created regions.
+    pub fn split_regions(
+        &self,
+        split_keys: Vec<Key>,
+    ) -> impl Future<Output = Result<Vec<RegionId>>> {
+        self.inner.pd.split_regions(
+            split_keys.into_iter().map(Key::into_inner).collect(),
+            SPLIT_REGIONS_RETRY_LIMIT,
+        )
+    }
+
+    /// Ask PD to scatter the region across the stores. Resolves once PD has scheduled it, see
+    /// [`wait_for_scatter`](RpcClient::wait_for_scatter) to wait until the region was moved.
+    pub fn scatter_region(&self, region_id: RegionId) -> impl Future<Output = Result<()>> {
+        self.inner.pd.scatter_region(region_id)
+    }
+
+    /// Resolve once PD has finished, or given up, scattering the region.
+    pub fn wait_for_scatter(&self, region_id: RegionId) -> impl Future<Output = Result<()>> {
+        loop_fn(self.inner(), move |inner| {
+            inner.pd.get_operator(region_id).and_then(move |resp| {
+                if resp.get_desc() != SCATTER_REGION_OPERATOR
+                    || resp.get_status() != pdpb::OperatorStatus::Running
+                {
+                    return Either::Left(future::ok(Loop::Break(())));
+                }
+                let delay = inner.runtime.delay(Instant::now() + SCATTER_CHECK_INTERVAL);
+                Either::Right(delay.map_ok(move |_| Loop::Continue(inner)))
+            })
+        })
+    }
+
+    pub fn get_gc_safe_point(&self) -> impl Future<Output = Result<u64>> {
+        self.inner.pd.get_gc_safe_point()
+    }
+
+    pub fn update_gc_safe_point(&self, safe_point: u64) -> impl Future<Output = Result<u64>> {
+        self.inner.pd.update_gc_safe_point(safe_point)
+    }
+
+    /// Set the safe point of a service for `ttl`, rounded up to whole seconds and at least one, as
+    /// PD would remove the safe point for a `ttl` of zero.
+    pub fn update_service_gc_safe_point(
+        &self,
+        service_id: String,
+        ttl: Duration,
+        safe_point: u64,
+    ) -> impl Future<Output = Result<u64>> {
+        let mut secs = ttl.as_secs();
+        if ttl.subsec_nanos() > 0 || secs == 0 {
+            secs = secs.saturating_add(1);
+        }
+        self.inner.pd.update_service_gc_safe_point(
+            service_id.into_bytes(),
+            cmp::min(secs, i64::max_value() as u64) as i64,
+            safe_point,
+        )
+    }
+
+    /// Remove the safe point of a service.
+    pub fn remove_service_gc_safe_point(
+        &self,
+        service_id: String,
+    ) -> impl Future<Output = Result<()>> {
+        self.inner
+            .pd
+            .update_service_gc_safe_point(service_id.into_bytes(), 0, 0)
+            .map_ok(|_| ())
+    }
+
+    /// Split `range` into at most `max_chunks` contiguous sub-ranges along region boundaries.
+    pub fn split_range(
+        &self,
+        range: (Key, Option<Key>),
+        max_chunks: usize,
+    ) -> impl Future<Output = Result<Vec<(Key, Option<Key>)>>> {
+        if let Some(end_key) = &range.1 {
+            if end_key < &range.0 {
+                return Either::Left(future::err(Error::invalid_key_range()));
+            } else if end_key == &range.0 {
+                return Either::Left(future::ok(Vec::new()));
+            }
+        }
+        let scan = ScanRegionsContext::new(range, ());
+        Either::Right(
+            loop_fn((self.inner(), scan), |(inner, mut scan)| {
+                inner
+                    .locate_key_in_range(scan.start_key(), scan.end_key())
+                    .map_ok(|region| {
+                        let region_range = (
+                            Key::from(region.start_key().to_vec()),
+                            Key::from(region.end_key().to_vec()),
+                        );
+                        let (start_key, end_key) = scan.range_in_region(&region_range.1);
+                        let end_key = if end_key.is_empty() {
+                            None
+                        } else {
+                            Some(end_key)
+                        };
+                        scan.result_mut().push((start_key.unwrap(), end_key));
+                        match scan.next(region_range) {
+                            ScanRegionsStatus::Continue => Loop::Continue((inner, scan)),
+                            ScanRegionsStatus::Break => Loop::Break(scan.into_inner()),
+                        }
+                    })
+            })
+            .map_ok(move |chunks| merge_chunks(chunks, max_chunks)),
+        )
+    }
+
     pub fn raw_get(
         &self,
         key: Key,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<Option<Value>>> {
-        Self::raw(self.inner(), &key, cf)
-            .and_then(|context| context.client().raw_get(context, key))
-            .map_ok(|value| if value.is_empty() { None } else { Some(value) })
+        let inner = self.inner();
+        Self::retry(inner.runtime(), inner.backoff(&options), move || {
+            let inner = Arc::clone(&inner);
+            let key = key.clone();
+            let options = options.clone();
+            inner.locate_key(&key).and_then(move |region| {
+                Self::raw_read(inner, region, options, move |context: RawContext| {
+                    context.client().raw_get(context, key.clone())
+                })
+            })
+        })
+        .map_ok(|value| if value.is_empty() { None } else { Some(value) })
     }
 
     pub fn raw_batch_get(
         &self,
         keys: Vec<Key>,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<Vec<KvPair>>> {
-        let inner = self.inner();
-        self.group_tasks_by_region(keys)
-            .and_then(move |task_groups| {
-                let mut tasks = Vec::with_capacity(task_groups.len());
-                for (region, keys) in task_groups.into_iter() {
-                    let inner = Arc::clone(&inner);
-                    let cf = cf.clone();
-                    let task = Self::region_context_by_id(inner, region.id)
-                        .map_ok(|(region, client)| RawContext::new(region, client, cf))
-                        .and_then(|context| {
-                            context.client().raw_batch_get(context, keys.into_iter())
-                        });
-                    tasks.push(task);
-                }
-                future::try_join_all(tasks)
+        self.raw_batch_get_partial(keys, options)
+            .and_then(|result| ready(result.into_result()))
+    }
+
+    pub fn raw_batch_get_partial(
+        &self,
+        keys: Vec<Key>,
+        options: RequestOptions,
+    ) -> impl Future<Output = Result<BatchResult<KvPair>>> {
+        let mode = self.inner.read_mode(&options);
+        let backoff = self.inner.backoff(&options);
+        Self::raw_batch_request(
+            self.inner(),
+            keys,
+            options,
+            |context: RawContext, keys: Vec<Key>| context.client().raw_batch_get(context, keys),
+            mode,
+            backoff,
+        )
+    }
+
+    pub fn raw_batch_get_ordered(
+        &self,
+        keys: Vec<Key>,
+        options: RequestOptions,
+    ) -> impl Future<Output = Result<Vec<Option<Value>>>> {
+        let mut unique_keys = keys.clone();
+        unique_keys.sort();
+        unique_keys.dedup();
+        self.raw_batch_get(unique_keys, options)
+            .map_ok(move |pairs| {
+                let values: HashMap<Key, Value> =
+                    pairs.into_iter().map(KvPair::into_inner).collect();
+                keys.iter().map(|key| values.get(key).cloned()).collect()
             })
-            .map_ok(|r| r.into_iter().flat_map(|a| a.into_iter()).collect())
     }
 
     pub fn raw_put(
         &self,
         key: Key,
         value: Value,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<()>> {
         if value.is_empty() {
             Either::Left(future::err(Error::empty_value()))
         } else {
-            Either::Right(
-                Self::raw(self.inner(), &key, cf)
-                    .and_then(|context| context.client().raw_put(context, key, value)),
-            )
+            let inner = self.inner();
+            Either::Right(Self::retry(
+                inner.runtime(),
+                inner.backoff(&options),
+                move || {
+                    let (key, value) = (key.clone(), value.clone());
+                    Self::raw(
+                        Arc::clone(&inner),
+                        &key.clone(),
+                        options.clone(),
+                        |context| context.client().raw_put(context, key, value),
+                    )
+                },
+            ))
         }
     }
 
     pub fn raw_batch_put(
         &self,
         pairs: Vec<KvPair>,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<()>> {
+        self.raw_batch_put_partial(pairs, options)
+            .and_then(|result| ready(result.into_result().map(|_| ())))
+    }
+
+    pub fn raw_batch_put_partial(
+        &self,
+        pairs: Vec<KvPair>,
+        options: RequestOptions,
+    ) -> impl Future<Output = Result<BatchResult<Key>>> {
         if pairs.iter().any(|p| p.value().is_empty()) {
             Either::Left(future::err(Error::empty_value()))
         } else {
-            let inner = self.inner();
-            Either::Right(
-                self.group_tasks_by_region(pairs)
-                    .and_then(move |task_groups| {
-                        let mut tasks = Vec::with_capacity(task_groups.len());
-                        for (region, pairs) in task_groups.into_iter() {
-                            let inner = Arc::clone(&inner);
-                            let cf = cf.clone();
-                            let task = Self::region_context_by_id(inner, region.id)
-                                .map_ok(|(region, client)| RawContext::new(region, client, cf))
-                                .and_then(|context| context.client().raw_batch_put(context, pairs));
-                            tasks.push(task);
-                        }
-                        future::try_join_all(tasks)
-                    })
-                    .map_ok(|_| ()),
-            )
+            let backoff = self.inner.backoff(&options);
+            Either::Right(Self::raw_batch_request(
+                self.inner(),
+                pairs,
+                options,
+                |context: RawContext, pairs: Vec<KvPair>| {
+                    let keys: Vec<Key> = pairs.iter().map(|pair| pair.key().clone()).collect();
+                    context
+                        .client()
+                        .raw_batch_put(context, pairs)
+                        .map_ok(move |_| keys)
+                },
+                ReplicaReadMode::Leader,
+                backoff,
+            ))
         }
     }
 
     pub fn raw_delete(
         &self,
         key: Key,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<()>> {
-        Self::raw(self.inner(), &key, cf)
-            .and_then(|context| context.client().raw_delete(context, key))
+        let inner = self.inner();
+        Self::retry(inner.runtime(), inner.backoff(&options), move || {
+            let key = key.clone();
+            Self::raw(
+                Arc::clone(&inner),
+                &key.clone(),
+                options.clone(),
+                |context| context.client().raw_delete(context, key),
+            )
+        })
     }
 
     pub fn raw_batch_delete(
         &self,
         keys: Vec<Key>,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<()>> {
-        let inner = self.inner();
-        self.group_tasks_by_region(keys)
-            .and_then(move |task_groups| {
-                let mut tasks = Vec::with_capacity(task_groups.len());
-                for (region, keys) in task_groups.into_iter() {
-                    let inner = Arc::clone(&inner);
-                    let cf = cf.clone();
-                    let task = Self::region_context_by_id(inner, region.id)
-                        .map_ok(|(region, client)| RawContext::new(region, client, cf))
-                        .and_then(|context| context.client().raw_batch_delete(context, keys));
-                    tasks.push(task);
+        self.raw_batch_delete_partial(keys, options)
+            .and_then(|result| ready(result.into_result().map(|_| ())))
+    }
+
+    pub fn raw_batch_delete_partial(
+        &self,
+        keys: Vec<Key>,
+        options: RequestOptions,
+    ) -> impl Future<Output = Result<BatchResult<Key>>> {
+        let backoff = self.inner.backoff(&options);
+        Self::raw_batch_request(
+            self.inner(),
+            keys,
+            options,
+            |context: RawContext, keys: Vec<Key>| {
+                let deleted = keys.clone();
+                context
+                    .client()
+                    .raw_batch_delete(context, keys)
+                    .map_ok(move |_| deleted)
+            },
+            ReplicaReadMode::Leader,
+            backoff,
+        )
+    }
+
+    /// Send a raw batch request, split by region and then into sub-batches bounded by the
+    /// [`BatchLimits`](BatchLimits) of the client.
+    ///
+    /// Reads are sent to the peers picked by `mode`, writes must use
+    /// [`ReplicaReadMode::Leader`](ReplicaReadMode::Leader).
+    ///
+    /// The sub-batches for each store are sent with bounded concurrency. A sub-batch which fails
+    /// with a region error (e.g., because its region was split) is regrouped and resent on its own
+    /// after a delay picked by `backoff`. A sub-batch which failed on a follower is resent to the
+    /// leader. The failure of a sub-batch does not affect the rest of the batch, it is reported in
+    /// the returned [`BatchResult`](BatchResult). The whole request only fails if the regions of
+    /// the keys cannot be located, in which case nothing was sent.
+    ///
+    /// `exec` hands the tasks of a failed sub-batch back with the error, so they are only kept
+    /// around for a retry if the sub-batch failed.
+    fn raw_batch_request<Task, Out, Exec, Fut>(
+        inner: Arc<RpcClientInner>,
+        tasks: Vec<Task>,
+        options: RequestOptions,
+        exec: Exec,
+        mode: ReplicaReadMode,
+        backoff: Backoff,
+    ) -> BoxTryFuture<BatchResult<Out>>
+    where
+        Task: GroupingTask + Send + Sync + 'static,
+        Out: Send + 'static,
+        Exec: Fn(RawContext, Vec<Task>) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<Vec<Out>, (Vec<Task>, Error)>> + Send + 'static,
+    {
+        let limits = inner.batch_limits;
+        let inner2 = Arc::clone(&inner);
+        Box::pin(
+            Self::group_tasks_by_region(Arc::clone(&inner), tasks)
+                .and_then(move |task_groups| {
+                    future::try_join_all(task_groups.into_iter().map(move |(region, tasks)| {
+                        Self::region_context_by_id(Arc::clone(&inner), region.id, mode)
+                            .map_ok(move |(region, client)| (region, client, tasks))
+                    }))
+                })
+                .and_then(move |regions| {
+                    let mut stores: HashMap<StoreId, Vec<_>> = HashMap::new();
+                    for (region, client, tasks) in regions {
+                        let batches = stores.entry(region.store.get_id()).or_default();
+                        for batch in limits.split(tasks) {
+                            batches.push((region.clone(), Arc::clone(&client), batch));
+                        }
+                    }
+                    future::join_all(stores.into_iter().map(move |(_, batches)| {
+                        let inner = Arc::clone(&inner2);
+                        let options = options.clone();
+                        let exec = exec.clone();
+                        let backoff = backoff.clone();
+                        stream::iter(batches.into_iter().map(move |(region, client, batch)| {
+                            Self::raw_sub_batch_request(
+                                Arc::clone(&inner),
+                                RawContext::new(region, client, options.clone()),
+                                batch,
+                                exec.clone(),
+                                mode,
+                                backoff.clone(),
+                            )
+                        }))
+                        .buffer_unordered(limits.concurrency)
+                        .collect::<BatchResult<Out>>()
+                    }))
+                    .map(|results| Ok(results.into_iter().collect()))
+                }),
+        )
+    }
+
+    /// Send a single sub-batch of [`raw_batch_request`](RpcClient::raw_batch_request), resending
+    /// it if it fails in a way which may succeed on retry.
+    fn raw_sub_batch_request<Task, Out, Exec, Fut>(
+        inner: Arc<RpcClientInner>,
+        context: RawContext,
+        batch: Vec<Task>,
+        exec: Exec,
+        mode: ReplicaReadMode,
+        mut backoff: Backoff,
+    ) -> impl Future<Output = BatchResult<Out>>
+    where
+        Task: GroupingTask + Send + Sync + 'static,
+        Out: Send + 'static,
+        Exec: Fn(RawContext, Vec<Task>) -> Fut + Clone + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<Vec<Out>, (Vec<Task>, Error)>> + Send + 'static,
+    {
+        let replica_read = context.region.is_replica_read();
+        let options = context.options.clone();
+        let region_id = context.region.region.id();
+        let store_id = context.region.store.get_id();
+        let client = context.client();
+        let retry_exec = exec.clone();
+        let send_inner = Arc::clone(&inner);
+        // Like `raw_send`, but the batch is kept if the request can't be sent.
+        inner
+            .acquire(store_id)
+            .then(move |permits| match permits {
+                Ok(permits) => Either::Left(exec(context, batch).map(move |result| {
+                    drop(permits);
+                    result
+                })),
+                Err(error) => Either::Right(future::err((batch, error))),
+            })
+            .map_err(move |(batch, error)| {
+                send_inner.on_connection_error(&client, &error);
+                (
+                    batch,
+                    send_inner.on_request_error(region_id, store_id, error),
+                )
+            })
+            .then(move |result| {
+                let (retry_batch, error) = match result {
+                    Ok(succeeded) => {
+                        return Either::Left(future::ready(BatchResult::from_succeeded(succeeded)));
+                    }
+                    Err(failed) => failed,
+                };
+                let (delay, mode) = if replica_read {
+                    warn!("replica read failed, retrying on the leader: {}", error);
+                    (Some(Duration::from_secs(0)), ReplicaReadMode::Leader)
+                } else {
+                    (backoff.next_delay(&error), mode)
+                };
+                let keys = retry_batch.iter().map(|task| task.key().clone()).collect();
+                match delay {
+                    Some(delay) => Either::Right(
+                        sleep(&*inner.runtime, delay)
+                            .and_then(move |_| {
+                                Self::raw_batch_request(
+                                    inner,
+                                    retry_batch,
+                                    options,
+                                    retry_exec,
+                                    mode,
+                                    backoff,
+                                )
+                            })
+                            .map(move |result| {
+                                result.unwrap_or_else(|error| BatchResult::from_failed(keys, error))
+                            }),
+                    ),
+                    None => Either::Left(future::ready(BatchResult::from_failed(keys, error))),
                 }
-                future::try_join_all(tasks)
             })
-            .map_ok(|_| ())
+    }
+
+    /// Run the request built by `request`, building and running it again after a delay for as long
+    /// as it fails with errors `backoff` considers worth retrying.
+    fn retry<Out, Fut>(
+        runtime: Arc<dyn Runtime>,
+        backoff: Backoff,
+        request: impl FnMut() -> Fut,
+    ) -> impl Future<Output = Result<Out>>
+    where
+        Fut: Future<Output = Result<Out>>,
+    {
+        loop_fn((backoff, request), |(mut backoff, mut request)| {
+            request().then(move |result| match result {
+                Ok(out) => Either::Left(future::ok(Loop::Break(out))),
+                Err(e) => match backoff.next_delay(&e) {
+                    Some(delay) => {
+                        info!("retry request after {:?}: {}", delay, e);
+                        Either::Right(
+                            sleep(&*runtime, delay)
+                                .map_ok(move |_| Loop::Continue((backoff, request))),
+                        )
+                    }
+                    None => Either::Left(future::err(e)),
+                },
+            })
+        })
     }
 
     pub fn raw_scan(
@@ -352,43 +1271,52 @@ impl RpcClient {
         range: (Key, Option<Key>),
         limit: u32,
         key_only: bool,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<Vec<KvPair>>> {
-        struct State {
-            limit: u32,
-            key_only: bool,
-            cf: Option<ColumnFamily>,
-        };
-        let scan: ScanRegionsContext<Vec<KvPair>, State> = ScanRegionsContext::new(
-            range,
-            State {
-                limit,
-                key_only,
-                cf,
-            },
-        );
-        let inner = Arc::clone(&self.inner);
-        loop_fn((inner, scan), |(inner, scan)| {
-            inner.locate_key(scan.start_key()).and_then(|location| {
-                let region = location;
-                let cf = scan.state.cf.clone();
-                Self::region_context_by_id(Arc::clone(&inner), region.id())
-                    .map_ok(|(region, client)| {
-                        (scan, region.range(), RawContext::new(region, client, cf))
-                    })
-                    .and_then(|(mut scan, region_range, context)| {
-                        let (start_key, end_key) = scan.range();
-                        context
-                            .client()
-                            .raw_scan(
+        let inner = self.inner();
+        Self::retry(inner.runtime(), inner.backoff(&options), move || {
+            let scan = ScanRegionsContext::new(
+                range.clone(),
+                RawScanState {
+                    limit,
+                    key_only,
+                    options: options.clone(),
+                },
+            );
+            Self::raw_scan_regions(Arc::clone(&inner), scan)
+        })
+    }
+
+    fn raw_scan_regions(
+        inner: Arc<RpcClientInner>,
+        scan: ScanRegionsContext<Vec<KvPair>, RawScanState>,
+    ) -> impl Future<Output = Result<Vec<KvPair>>> {
+        loop_fn((inner, scan), |(inner, mut scan)| {
+            inner
+                .locate_key_in_range(scan.start_key(), scan.end_key())
+                .and_then(move |region| {
+                    let region_range = (
+                        Key::from(region.start_key().to_vec()),
+                        Key::from(region.end_key().to_vec()),
+                    );
+                    let (start_key, end_key) = scan.range();
+                    let (limit, key_only) = (scan.state.limit, scan.state.key_only);
+                    let options = scan.state.options.clone();
+                    Self::raw_read(
+                        Arc::clone(&inner),
+                        region,
+                        options,
+                        move |context: RawContext| {
+                            context.client().raw_scan(
                                 context,
-                                start_key,
-                                end_key,
-                                scan.state.limit,
-                                scan.state.key_only,
+                                start_key.clone(),
+                                end_key.clone(),
+                                limit,
+                                key_only,
                             )
-                            .map_ok(|pairs| (scan, region_range, pairs))
-                    })
+                        },
+                    )
+                    .map_ok(|pairs| (scan, region_range, pairs))
                     .map_ok(|(mut scan, region_range, mut pairs)| {
                         let limit = scan.state.limit;
                         scan.result_mut().append(&mut pairs);
@@ -401,7 +1329,7 @@ impl RpcClient {
                             }
                         }
                     })
-            })
+                })
         })
     }
 
@@ -410,46 +1338,90 @@ impl RpcClient {
         ranges: Vec<(Key, Option<Key>)>,
         _each_limit: u32,
         _key_only: bool,
-        cf: Option<ColumnFamily>,
+        options: RequestOptions,
     ) -> impl Future<Output = Result<Vec<KvPair>>> {
         drop(ranges);
-        drop(cf);
+        drop(options);
         future::err(Error::unimplemented())
     }
 
     pub fn raw_delete_range(
         &self,
         range: (Key, Option<Key>),
-        cf: Option<ColumnFamily>,
-    ) -> impl Future<Output = Result<()>> {
-        let scan: ScanRegionsContext<(), Option<ColumnFamily>> = ScanRegionsContext::new(range, cf);
-        let inner = Arc::clone(&self.inner);
+        options: RequestOptions,
+    ) -> impl Future<Output = Result<usize>> {
+        if let Some(end_key) = &range.1 {
+            if end_key < &range.0 {
+                return Either::Left(future::err(Error::invalid_key_range()));
+            } else if end_key == &range.0 {
+                return Either::Left(future::ok(0));
+            }
+        }
+        let inner = self.inner();
+        Either::Right(Self::retry(
+            inner.runtime(),
+            inner.backoff(&options),
+            move || {
+                let scan = ScanRegionsContext::new(range.clone(), options.clone());
+                Self::raw_delete_range_regions(Arc::clone(&inner), scan)
+            },
+        ))
+    }
+
+    fn raw_delete_range_regions(
+        inner: Arc<RpcClientInner>,
+        scan: ScanRegionsContext<usize, RequestOptions>,
+    ) -> impl Future<Output = Result<usize>> {
         loop_fn((inner, scan), |(inner, scan)| {
-            inner.locate_key(scan.start_key()).and_then(|location| {
-                let region = location;
-                let cf = scan.state.clone();
-                Self::region_context_by_id(Arc::clone(&inner), region.id())
+            inner
+                .locate_key_in_range(scan.start_key(), scan.end_key())
+                .and_then(|location| {
+                    let region = location;
+                    let options = scan.state.clone();
+                    let inner2 = Arc::clone(&inner);
+                    Self::region_context_by_id(
+                        Arc::clone(&inner),
+                        region.id(),
+                        ReplicaReadMode::Leader,
+                    )
                     .map_ok(|(region, client)| {
-                        (scan, region.range(), RawContext::new(region, client, cf))
+                        (
+                            scan,
+                            region.range(),
+                            RawContext::new(region, client, options),
+                        )
                     })
                     .and_then(|(mut scan, region_range, context)| {
-                        let (start_key, end_key) = scan.range();
-                        let start_key = start_key.expect("start key must be specified");
-                        let end_key = end_key.expect("end key must be specified");
-                        context
-                            .client()
-                            .raw_delete_range(context, start_key, end_key)
+                        let (start_key, end_key) = scan.range_in_region(&region_range.1);
+                        ready(start_key.ok_or_else(Error::invalid_key_range))
+                            .and_then(move |start_key| {
+                                Self::raw_send(inner2, context, |context| {
+                                    context
+                                        .client()
+                                        .raw_delete_range(context, start_key, end_key)
+                                })
+                            })
                             .map_ok(|_| (scan, region_range))
                     })
-                    .map_ok(|(mut scan, region_range)| match scan.next(region_range) {
-                        ScanRegionsStatus::Continue => Loop::Continue((inner, scan)),
-                        ScanRegionsStatus::Break => Loop::Break(()),
+                    .map_ok(|(mut scan, region_range)| {
+                        *scan.result_mut() += 1;
+                        match scan.next(region_range) {
+                            ScanRegionsStatus::Continue => Loop::Continue((inner, scan)),
+                            ScanRegionsStatus::Break => Loop::Break(scan.into_inner()),
+                        }
                     })
-            })
+                })
         })
     }
 }
 
+/// The state of a [`raw_scan`](RpcClient::raw_scan) while it visits the regions of its range.
+struct RawScanState {
+    limit: u32,
+    key_only: bool,
+    options: RequestOptions,
+}
+
 impl fmt::Debug for RpcClient {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("tikv-client")
@@ -458,12 +1430,27 @@ impl fmt::Debug for RpcClient {
     }
 }
 
+#[derive(Clone)]
 pub struct RegionContext {
     region: Region,
+    peer: Peer,
     store: Store,
 }
 
 impl RegionContext {
+    fn new(region: Region, peer: Peer, store: Store) -> Self {
+        RegionContext {
+            region,
+            peer,
+            store,
+        }
+    }
+
+    /// Whether the request is sent to a peer other than the leader.
+    fn is_replica_read(&self) -> bool {
+        self.region.leader.as_ref().map(Peer::get_id) != Some(self.peer.get_id())
+    }
+
     fn address(&self) -> &str {
         self.store.get_address()
     }
@@ -486,7 +1473,8 @@ impl From<RegionContext> for kvrpcpb::Context {
         let mut kvctx = kvrpcpb::Context::default();
         kvctx.set_region_id(ctx.region.id());
         kvctx.set_region_epoch(ctx.region.region.take_region_epoch());
-        kvctx.set_peer(ctx.region.peer().expect("leader must exist"));
+        kvctx.set_replica_read(ctx.is_replica_read());
+        kvctx.set_peer(ctx.peer);
         kvctx
     }
 }
@@ -494,20 +1482,24 @@ impl From<RegionContext> for kvrpcpb::Context {
 pub struct RawContext {
     region: RegionContext,
     client: Arc<KvClient>,
-    cf: Option<ColumnFamily>,
+    options: RequestOptions,
 }
 
 impl RawContext {
-    fn new(region: RegionContext, client: Arc<KvClient>, cf: Option<ColumnFamily>) -> Self {
-        RawContext { region, client, cf }
+    fn new(region: RegionContext, client: Arc<KvClient>, options: RequestOptions) -> Self {
+        RawContext {
+            region,
+            client,
+            options,
+        }
     }
 
     fn client(&self) -> Arc<KvClient> {
         Arc::clone(&self.client)
     }
 
-    pub fn into_inner(self) -> (RegionContext, Option<ColumnFamily>) {
-        (self.region, self.cf)
+    pub fn into_inner(self) -> (RegionContext, RequestOptions) {
+        (self.region, self.options)
     }
 }
 
@@ -529,6 +1521,11 @@ type KeyLocation = Region;
 
 trait GroupingTask: Clone + Default + Sized {
     fn key(&self) -> &Key;
+
+    /// The approximate number of bytes the task adds to a request.
+    fn size(&self) -> usize {
+        self.key().len()
+    }
 }
 
 #[derive(Default)]
@@ -574,6 +1571,10 @@ impl GroupingTask for KvPair {
     fn key(&self) -> &Key {
         self.key()
     }
+
+    fn size(&self) -> usize {
+        self.key().len() + self.value().len()
+    }
 }
 
 impl GroupingTask for (Key, Option<Key>) {
@@ -616,6 +1617,16 @@ where
         (self.start_key.take(), self.end_key.clone())
     }
 
+    /// Like `range`, but the end key is clamped to the end of the region being visited. An empty
+    /// end key means the range is unbounded, just like an empty region end key.
+    fn range_in_region(&mut self, region_end: &Key) -> (Option<Key>, Key) {
+        let end_key = match &self.end_key {
+            Some(end_key) if region_end.is_empty() || end_key < region_end => end_key.clone(),
+            _ => region_end.clone(),
+        };
+        (self.start_key.take(), end_key)
+    }
+
     fn start_key(&self) -> &Key {
         self.start_key.as_ref().unwrap()
     }
@@ -627,7 +1638,7 @@ where
     fn next(&mut self, region_range: (Key, Key)) -> ScanRegionsStatus {
         {
             let region_end = &region_range.1;
-            if self.end_key().map(|x| x < region_end).unwrap_or(false) || region_end.is_empty() {
+            if self.end_key().map(|x| x <= region_end).unwrap_or(false) || region_end.is_empty() {
                 return ScanRegionsStatus::Break;
             }
         }
@@ -647,3 +1658,181 @@ where
         &self.result
     }
 }
+
+/// Merge consecutive `chunks` into at most `max_chunks` chunks of about the same number of
+/// chunks each.
+fn merge_chunks(chunks: Vec<(Key, Option<Key>)>, max_chunks: usize) -> Vec<(Key, Option<Key>)> {
+    let max_chunks = cmp::max(max_chunks, 1);
+    if chunks.len() <= max_chunks {
+        return chunks;
+    }
+    let len = chunks.len();
+    let mut merged = Vec::with_capacity(max_chunks);
+    let mut chunks = chunks.into_iter();
+    for i in 0..max_chunks {
+        let count = (i + 1) * len / max_chunks - i * len / max_chunks;
+        let (start_key, mut end_key) = chunks.next().unwrap();
+        for (_, next_end_key) in chunks.by_ref().take(count - 1) {
+            end_key = next_end_key;
+        }
+        merged.push((start_key, end_key));
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(bounds: &[&'static str]) -> Vec<(Key, Option<Key>)> {
+        bounds
+            .windows(2)
+            .map(|w| {
+                let end_key = if w[1].is_empty() {
+                    None
+                } else {
+                    Some(w[1].into())
+                };
+                (w[0].into(), end_key)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_chunks() {
+        let regions = chunks(&["", "b", "c", "d", "e", ""]);
+        assert_eq!(merge_chunks(regions.clone(), 5), regions);
+        assert_eq!(merge_chunks(regions.clone(), 10), regions);
+        assert_eq!(merge_chunks(regions.clone(), 2), chunks(&["", "c", ""]));
+        assert_eq!(
+            merge_chunks(regions.clone(), 3),
+            chunks(&["", "b", "d", ""])
+        );
+        assert_eq!(merge_chunks(regions.clone(), 1), chunks(&["", ""]));
+        assert_eq!(merge_chunks(regions, 0), chunks(&["", ""]));
+        assert!(merge_chunks(Vec::new(), 4).is_empty());
+    }
+
+    fn scan_context(start: &'static str, end: Option<&'static str>) -> ScanRegionsContext<(), ()> {
+        ScanRegionsContext::new((start.into(), end.map(Into::into)), ())
+    }
+
+    #[test]
+    fn test_range_in_region() {
+        let mut scan = scan_context("a", Some("c"));
+        assert_eq!(
+            scan.range_in_region(&"b".into()),
+            (Some("a".into()), "b".into())
+        );
+
+        let mut scan = scan_context("a", Some("c"));
+        assert_eq!(
+            scan.range_in_region(&"d".into()),
+            (Some("a".into()), "c".into())
+        );
+
+        let mut scan = scan_context("a", Some("c"));
+        assert_eq!(
+            scan.range_in_region(&Key::default()),
+            (Some("a".into()), "c".into())
+        );
+
+        let mut scan = scan_context("a", None);
+        assert_eq!(
+            scan.range_in_region(&"b".into()),
+            (Some("a".into()), "b".into())
+        );
+
+        let mut scan = scan_context("a", None);
+        assert_eq!(
+            scan.range_in_region(&Key::default()),
+            (Some("a".into()), Key::default())
+        );
+    }
+
+    #[test]
+    fn test_scan_regions_next() {
+        let mut scan = scan_context("a", Some("c"));
+        match scan.next(("a".into(), "b".into())) {
+            ScanRegionsStatus::Continue => assert_eq!(scan.start_key(), &Key::from("b")),
+            ScanRegionsStatus::Break => panic!("the range continues past the region"),
+        }
+        match scan.next(("b".into(), "c".into())) {
+            ScanRegionsStatus::Continue => panic!("the range ends with the region"),
+            ScanRegionsStatus::Break => {}
+        }
+
+        let mut scan = scan_context("a", None);
+        match scan.next(("a".into(), "b".into())) {
+            ScanRegionsStatus::Continue => assert_eq!(scan.start_key(), &Key::from("b")),
+            ScanRegionsStatus::Break => panic!("the range is unbounded"),
+        }
+        match scan.next(("b".into(), Key::default())) {
+            ScanRegionsStatus::Continue => panic!("the last region was visited"),
+            ScanRegionsStatus::Break => {}
+        }
+    }
+
+    #[test]
+    fn test_batch_limits_split() {
+        let pair = |key: &'static str, value: &'static str| KvPair::new(key, value);
+        let pairs = vec![
+            pair("a", "1"),
+            pair("b", "22"),
+            pair("c", "333"),
+            pair("d", "4444444444"),
+            pair("e", "5"),
+        ];
+        let limits = |max_keys, max_bytes| BatchLimits {
+            max_keys,
+            max_bytes,
+            concurrency: 1,
+        };
+
+        let batches = limits(2, 0).split(pairs.clone());
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+
+        // A pair exceeding the byte limit on its own gets a batch of its own.
+        let batches = limits(0, 6).split(pairs.clone());
+        assert_eq!(
+            batches.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![2, 1, 1, 1]
+        );
+
+        let batches = limits(0, 0).split(pairs.clone());
+        assert_eq!(batches, vec![pairs]);
+        assert!(limits(1, 1).split(Vec::<KvPair>::new()).is_empty());
+    }
+
+    #[test]
+    fn test_replica_read_context() {
+        let peer = |id, store_id| {
+            let mut peer = Peer::default();
+            peer.set_id(id);
+            peer.set_store_id(store_id);
+            peer
+        };
+        let mut meta = kvproto::metapb::Region::default();
+        let mut learner = peer(4, 4);
+        learner.set_is_learner(true);
+        meta.set_peers(vec![peer(1, 1), peer(2, 2), peer(3, 3), learner].into());
+        let region = Region::new(meta, Some(peer(2, 2)));
+
+        let followers: Vec<u64> = region.followers().into_iter().map(Peer::get_id).collect();
+        assert_eq!(followers, vec![1, 3]);
+
+        let context = RegionContext::new(region.clone(), peer(2, 2), Store::default());
+        assert!(!context.is_replica_read());
+        let kvctx: kvrpcpb::Context = context.into();
+        assert!(!kvctx.get_replica_read());
+
+        let context = RegionContext::new(region, peer(3, 3), Store::default());
+        assert!(context.is_replica_read());
+        let kvctx: kvrpcpb::Context = context.into();
+        assert!(kvctx.get_replica_read());
+        assert_eq!(kvctx.get_peer().get_id(), 3);
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::channel::oneshot;
+use futures::future::{self, Either, Future};
+use futures::prelude::FutureExt;
+
+use crate::{
+    rpc::{backoff::sleep, Runtime},
+    Error, Result,
+};
+
+/// Limits the number of requests in flight to `limit`.
+///
+/// Requests beyond the limit wait in a queue and are let through first-come, first-served as the
+/// requests in flight finish. A request which waited for longer than its timeout fails with
+/// [`ErrorKind::QueueTimeout`](crate::ErrorKind::QueueTimeout).
+pub struct Limiter {
+    limit: usize,
+    /// What is limited, for error messages.
+    target: String,
+    state: Mutex<LimiterState>,
+    runtime: Arc<dyn Runtime>,
+}
+
+struct LimiterState {
+    in_flight: usize,
+    /// Invariant: only non-empty if `in_flight == limit`.
+    waiting: VecDeque<oneshot::Sender<()>>,
+}
+
+/// Permission to have a request in flight, given back to the [`Limiter`](Limiter) when dropped.
+pub struct Permit {
+    limiter: Arc<Limiter>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.limiter.release();
+    }
+}
+
+impl Limiter {
+    pub fn new(limit: usize, target: impl Into<String>, runtime: Arc<dyn Runtime>) -> Limiter {
+        Limiter {
+            limit,
+            target: target.into(),
+            state: Mutex::new(LimiterState {
+                in_flight: 0,
+                waiting: VecDeque::new(),
+            }),
+            runtime,
+        }
+    }
+
+    /// Wait for permission to send a request, for at most `timeout`.
+    pub fn acquire(self: &Arc<Self>, timeout: Duration) -> impl Future<Output = Result<Permit>> {
+        let limiter = Arc::clone(self);
+        let rx = {
+            let mut state = self.state.lock().unwrap();
+            if state.in_flight < self.limit {
+                state.in_flight += 1;
+                return Either::Left(future::ok(Permit { limiter }));
+            }
+            let (tx, rx) = oneshot::channel();
+            state.waiting.push_back(tx);
+            rx
+        };
+        Either::Right(
+            future::select(rx, Box::pin(sleep(&*self.runtime, timeout))).map(move |r| match r {
+                Either::Left((Ok(()), _)) => Ok(Permit { limiter }),
+                Either::Left((Err(canceled), _)) => Err(canceled.into()),
+                Either::Right((_, mut rx)) => {
+                    // The permit may have been handed over just now.
+                    rx.close();
+                    match rx.try_recv() {
+                        Ok(Some(())) => Ok(Permit { limiter }),
+                        _ => Err(Error::queue_timeout(
+                            limiter.target.clone(),
+                            limiter.limit,
+                            timeout,
+                        )),
+                    }
+                }
+            }),
+        )
+    }
+
+    /// Hand a finished request's permit over to the first waiting request, if any.
+    fn release(&self) {
+        let mut state = self.state.lock().unwrap();
+        while let Some(waiting) = state.waiting.pop_front() {
+            // Fails if the request gave up waiting.
+            if waiting.send(()).is_ok() {
+                return;
+            }
+        }
+        state.in_flight -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rpc::DefaultRuntime, ErrorKind};
+    use futures::executor::block_on;
+
+    #[test]
+    fn test_limiter() {
+        let limiter = Arc::new(Limiter::new(
+            2,
+            "store 1",
+            Arc::new(DefaultRuntime::new().unwrap()),
+        ));
+        let first = block_on(limiter.acquire(Duration::from_secs(1))).unwrap();
+        let _second = block_on(limiter.acquire(Duration::from_secs(1))).unwrap();
+
+        match block_on(limiter.acquire(Duration::from_millis(10))) {
+            Err(e) => match e.kind() {
+                ErrorKind::QueueTimeout { limit, .. } => assert_eq!(*limit, 2),
+                _ => panic!("unexpected error: {}", e),
+            },
+            Ok(_) => panic!("the limit was exceeded"),
+        }
+
+        // Waiting requests are let through in order.
+        let third = limiter.acquire(Duration::from_secs(1));
+        let fourth = limiter.acquire(Duration::from_millis(50));
+        drop(first);
+        let third = block_on(third).unwrap();
+        assert!(block_on(fourth).is_err());
+
+        drop(third);
+        assert!(block_on(limiter.acquire(Duration::from_millis(10))).is_ok());
+    }
+}
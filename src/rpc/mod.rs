@@ -2,10 +2,18 @@
 
 #[macro_use]
 mod util;
+mod backoff;
 mod client;
 mod context;
+mod limiter;
 mod pd;
+mod pool;
+mod region_cache;
+mod runtime;
 mod security;
 mod tikv;
 
-pub(crate) use crate::rpc::client::RpcClient;
+pub use crate::rpc::runtime::{DefaultRuntime, Runtime};
+pub(crate) use crate::rpc::{
+    client::RpcClient, pd::Region, runtime::RuntimeHandle, util::with_deadline,
+};
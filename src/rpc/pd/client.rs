@@ -6,7 +6,6 @@ use std::{
     time::Duration,
 };
 
-use futures::compat::Compat01As03;
 use futures::future::{ready, Future};
 use futures::prelude::{FutureExt, TryFutureExt};
 use grpcio::{CallOption, Environment};
@@ -18,10 +17,11 @@ use crate::{
             context::{request_context, PdRequestContext},
             leader::LeaderClient,
             request::Request,
-            PdTimestamp, Region, RegionId, Store, StoreId,
+            PdTimestamp, Region, RegionId, Store, StoreId, TsoOptions,
         },
         security::SecurityManager,
-        util::HandyRwLock,
+        util::{cancel_on_drop, HandyRwLock},
+        Runtime,
     },
     Error, ErrorKind, Result,
 };
@@ -30,6 +30,11 @@ const LEADER_CHANGE_RETRY: usize = 10;
 
 trait PdResponse {
     fn header(&self) -> &pdpb::ResponseHeader;
+
+    /// Whether an error in the header is an expected outcome rather than a failure of the request.
+    fn is_expected_error(&self, _error: &pdpb::Error) -> bool {
+        false
+    }
 }
 
 impl PdResponse for pdpb::GetStoreResponse {
@@ -44,16 +49,64 @@ impl PdResponse for pdpb::GetRegionResponse {
     }
 }
 
+impl PdResponse for pdpb::ScanRegionsResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+}
+
 impl PdResponse for pdpb::GetAllStoresResponse {
     fn header(&self) -> &pdpb::ResponseHeader {
         self.get_header()
     }
 }
 
+impl PdResponse for pdpb::SplitRegionsResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+}
+
+impl PdResponse for pdpb::ScatterRegionResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+}
+
+impl PdResponse for pdpb::GetOperatorResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+
+    // PD reports a region without an operator as not found.
+    fn is_expected_error(&self, error: &pdpb::Error) -> bool {
+        error.get_type() == pdpb::ErrorType::RegionNotFound
+    }
+}
+
+impl PdResponse for pdpb::GetGcSafePointResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+}
+
+impl PdResponse for pdpb::UpdateGcSafePointResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+}
+
+impl PdResponse for pdpb::UpdateServiceGcSafePointResponse {
+    fn header(&self) -> &pdpb::ResponseHeader {
+        self.get_header()
+    }
+}
+
 pub struct PdClient {
     cluster_id: u64,
     leader: Arc<RwLock<LeaderClient>>,
     timeout: Duration,
+    runtime: Arc<dyn Runtime>,
 }
 
 impl PdClient {
@@ -62,14 +115,24 @@ impl PdClient {
         endpoints: &[String],
         security_mgr: Arc<SecurityManager>,
         timeout: Duration,
+        runtime: Arc<dyn Runtime>,
+        tso_options: TsoOptions,
     ) -> Result<PdClient> {
-        let leader = LeaderClient::connect(env, endpoints, security_mgr, timeout)?;
+        let leader = LeaderClient::connect(
+            env,
+            endpoints,
+            security_mgr,
+            timeout,
+            Arc::clone(&runtime),
+            tso_options,
+        )?;
         let cluster_id = leader.rl().cluster_id();
 
         Ok(PdClient {
             cluster_id,
             leader,
             timeout,
+            runtime,
         })
     }
 
@@ -87,9 +150,7 @@ impl PdClient {
 
         self.execute(request_context(
             "get_region",
-            move |cli: &RpcClient, opt: _| {
-                cli.get_region_async_opt(&req, opt).map(Compat01As03::new)
-            },
+            move |cli: &RpcClient, opt: _| cli.get_region_async_opt(&req, opt).map(cancel_on_drop),
         ))
         .and_then(move |mut resp| {
             let region = if resp.has_region() {
@@ -117,7 +178,7 @@ impl PdClient {
             "get_region_by_id",
             move |cli: &RpcClient, opt: _| {
                 cli.get_region_by_id_async_opt(&req, opt)
-                    .map(Compat01As03::new)
+                    .map(cancel_on_drop)
             },
         ))
         .and_then(move |mut resp| {
@@ -154,7 +215,7 @@ impl PdClient {
                 Ok(r) => {
                     {
                         let header = r.header();
-                        if header.has_error() {
+                        if header.has_error() && !r.is_expected_error(header.get_error()) {
                             return Err(internal_err!(header.get_error().get_message()));
                         }
                     }
@@ -167,6 +228,7 @@ impl PdClient {
             Arc::clone(&self.leader),
             LeaderClient::reconnect,
             LEADER_CHANGE_RETRY,
+            Arc::clone(&self.runtime),
         )
         .execute()
         .map(move |r| context.done(r))
@@ -178,8 +240,7 @@ impl PdClient {
         self.execute(request_context(
             "get_all_stores",
             move |cli: &RpcClient, opt: _| {
-                cli.get_all_stores_async_opt(&req, opt)
-                    .map(Compat01As03::new)
+                cli.get_all_stores_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
         .map_ok(|mut resp| resp.take_stores().into_iter().map(Into::into).collect())
@@ -191,9 +252,7 @@ impl PdClient {
 
         self.execute(request_context(
             "get_store",
-            move |cli: &RpcClient, opt: _| {
-                cli.get_store_async_opt(&req, opt).map(Compat01As03::new)
-            },
+            move |cli: &RpcClient, opt: _| cli.get_store_async_opt(&req, opt).map(cancel_on_drop),
         ))
         .map_ok(|mut resp| resp.take_store())
     }
@@ -208,6 +267,150 @@ impl PdClient {
             .map_ok(|x| Region::new(x.0, x.1))
     }
 
+    /// Get at most `limit` consecutive regions, starting from the one containing `start_key`
+    /// and ending before `end_key`. An empty `end_key` means the scan is unbounded.
+    pub fn scan_regions(
+        &self,
+        start_key: &[u8],
+        end_key: &[u8],
+        limit: i32,
+    ) -> impl Future<Output = Result<Vec<Region>>> {
+        let mut req = pd_request!(self.cluster_id, pdpb::ScanRegionsRequest);
+        req.set_start_key(start_key.to_owned());
+        req.set_end_key(end_key.to_owned());
+        req.set_limit(limit);
+
+        self.execute(request_context(
+            "scan_regions",
+            move |cli: &RpcClient, opt: _| {
+                cli.scan_regions_async_opt(&req, opt).map(cancel_on_drop)
+            },
+        ))
+        .map_ok(|mut resp| {
+            let mut leaders = resp.take_leaders().into_iter();
+            resp.take_region_metas()
+                .into_iter()
+                .map(|region| {
+                    // PD sends an empty peer for regions without a known leader.
+                    let leader = leaders.next().filter(|leader| leader.get_id() != 0);
+                    Region::new(region, leader)
+                })
+                .collect()
+        })
+    }
+
+    /// Split the regions containing `split_keys` at those keys, and return the ids of the newly
+    /// created regions. PD retries failed splits at most `retry_limit` times.
+    pub fn split_regions(
+        &self,
+        split_keys: Vec<Vec<u8>>,
+        retry_limit: u64,
+    ) -> impl Future<Output = Result<Vec<RegionId>>> {
+        let mut req = pd_request!(self.cluster_id, pdpb::SplitRegionsRequest);
+        req.set_split_keys(split_keys.into());
+        req.set_retry_limit(retry_limit);
+
+        self.execute(request_context(
+            "split_regions",
+            move |cli: &RpcClient, opt: _| {
+                cli.split_regions_async_opt(&req, opt).map(cancel_on_drop)
+            },
+        ))
+        .and_then(|mut resp| {
+            if resp.get_finished_percentage() < 100 {
+                return ready(Err(internal_err!(
+                    "only {}% of the split keys were split",
+                    resp.get_finished_percentage()
+                )));
+            }
+            ready(Ok(resp.take_regions_id()))
+        })
+    }
+
+    /// Ask PD to move the peers and leader of a region to random stores. PD moves them in the
+    /// background, see `get_operator`.
+    pub fn scatter_region(&self, region_id: RegionId) -> impl Future<Output = Result<()>> {
+        let mut req = pd_request!(self.cluster_id, pdpb::ScatterRegionRequest);
+        req.set_region_id(region_id);
+
+        self.execute(request_context(
+            "scatter_region",
+            move |cli: &RpcClient, opt: _| {
+                cli.scatter_region_async_opt(&req, opt).map(cancel_on_drop)
+            },
+        ))
+        .map_ok(|_| ())
+    }
+
+    /// The operator PD most recently scheduled for a region, e.g. to scatter it. The response has
+    /// a `RegionNotFound` error in its header if there is no such operator.
+    pub fn get_operator(
+        &self,
+        region_id: RegionId,
+    ) -> impl Future<Output = Result<pdpb::GetOperatorResponse>> {
+        let mut req = pd_request!(self.cluster_id, pdpb::GetOperatorRequest);
+        req.set_region_id(region_id);
+
+        self.execute(request_context(
+            "get_operator",
+            move |cli: &RpcClient, opt: _| {
+                cli.get_operator_async_opt(&req, opt).map(cancel_on_drop)
+            },
+        ))
+    }
+
+    pub fn get_gc_safe_point(&self) -> impl Future<Output = Result<u64>> {
+        let req = pd_request!(self.cluster_id, pdpb::GetGcSafePointRequest);
+
+        self.execute(request_context(
+            "get_gc_safe_point",
+            move |cli: &RpcClient, opt: _| {
+                cli.get_gc_safe_point_async_opt(&req, opt)
+                    .map(cancel_on_drop)
+            },
+        ))
+        .map_ok(|resp| resp.get_safe_point())
+    }
+
+    /// Advance the GC safe point, and return the new one. PD never moves the safe point back.
+    pub fn update_gc_safe_point(&self, safe_point: u64) -> impl Future<Output = Result<u64>> {
+        let mut req = pd_request!(self.cluster_id, pdpb::UpdateGcSafePointRequest);
+        req.set_safe_point(safe_point);
+
+        self.execute(request_context(
+            "update_gc_safe_point",
+            move |cli: &RpcClient, opt: _| {
+                cli.update_gc_safe_point_async_opt(&req, opt)
+                    .map(cancel_on_drop)
+            },
+        ))
+        .map_ok(|resp| resp.get_new_safe_point())
+    }
+
+    /// Set the safe point of a service, which GC may not pass for `ttl` seconds,
+    /// and return the minimum safe point of all services. A `ttl` which is not positive removes
+    /// the safe point of the service.
+    pub fn update_service_gc_safe_point(
+        &self,
+        service_id: Vec<u8>,
+        ttl: i64,
+        safe_point: u64,
+    ) -> impl Future<Output = Result<u64>> {
+        let mut req = pd_request!(self.cluster_id, pdpb::UpdateServiceGcSafePointRequest);
+        req.set_service_id(service_id);
+        req.set_ttl(ttl);
+        req.set_safe_point(safe_point);
+
+        self.execute(request_context(
+            "update_service_gc_safe_point",
+            move |cli: &RpcClient, opt: _| {
+                cli.update_service_gc_safe_point_async_opt(&req, opt)
+                    .map(cancel_on_drop)
+            },
+        ))
+        .map_ok(|resp| resp.get_min_safe_point())
+    }
+
     pub fn get_ts(&self) -> impl Future<Output = Result<PdTimestamp>> {
         self.leader.wl().get_ts()
     }
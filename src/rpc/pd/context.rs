@@ -46,6 +46,10 @@ pub fn observe_tso_batch(batch_size: usize) -> u32 {
     batch_size as u32
 }
 
+pub fn observe_tso_reconnect() {
+    PD_TSO_RECONNECT_COUNTER.inc();
+}
+
 lazy_static! {
     static ref PD_REQUEST_DURATION_HISTOGRAM_VEC: HistogramVec = register_histogram_vec!(
         "pd_request_duration_seconds",
@@ -76,4 +80,9 @@ lazy_static! {
         "Bucketed histogram of TSO request batch size"
     )
     .unwrap();
+    static ref PD_TSO_RECONNECT_COUNTER: IntCounter = register_int_counter!(
+        "pd_tso_stream_reconnect_total",
+        "Total number of times the TSO stream failed and was re-established"
+    )
+    .unwrap();
 }
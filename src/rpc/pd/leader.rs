@@ -1,9 +1,10 @@
 // Copyright 2018 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::{
+    cmp,
     collections::HashSet,
-    sync::{Arc, RwLock},
-    thread::{self, JoinHandle},
+    sync::{Arc, RwLock, Weak},
+    thread,
     time::{Duration, Instant},
 };
 
@@ -14,24 +15,25 @@ use futures::{
         oneshot,
     },
     compat::{Compat01As03, Compat01As03Sink},
-    future::ready,
+    future::{ready, Either},
     stream::TryStreamExt,
     Future,
 };
 use grpcio::{CallOption, Environment, WriteFlags};
 use kvproto::pdpb;
 use log::*;
-use tokio_core::reactor::{Core, Handle as OtherHandle};
 
 use crate::{
-    compat::SinkCompat,
+    compat::{loop_fn, Loop, SinkCompat},
     rpc::{
         pd::{
-            context::{observe_tso_batch, request_context},
+            context::{observe_tso_batch, observe_tso_reconnect, request_context},
+            request::RECONNECT_INTERVAL_SEC,
             PdTimestamp,
         },
         security::SecurityManager,
-        util::HandyRwLock,
+        util::{cancel_on_drop, HandyRwLock},
+        Runtime,
     },
     Error, Result,
 };
@@ -46,12 +48,89 @@ macro_rules! pd_request {
     }};
 }
 
+/// How often the members of the PD cluster are fetched, see
+/// [`LeaderClient::watch_members`](LeaderClient::watch_members).
+const MEMBERS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long after PD allocated them prefetched timestamps are still handed out, which bounds how
+/// far they may trail the timestamps other clients get from PD in the meantime.
+const TSO_PREFETCH_TTL: Duration = Duration::from_millis(3);
+
 type TsoChannel = oneshot::Sender<PdTimestamp>;
 
+/// How requests for timestamps are batched and prefetched, see
+/// [`Config::tso_batch`](crate::Config::tso_batch) and
+/// [`Config::tso_prefetch`](crate::Config::tso_prefetch).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TsoOptions {
+    /// How long the first request of a batch waits for more requests before the batch is sent.
+    pub max_wait: Duration,
+    /// The maximum number of requests in a batch, or `0` for no limit.
+    pub max_batch_size: usize,
+    /// How many timestamps are requested from PD ahead of time with every batch.
+    pub prefetch: usize,
+}
+
+/// A block of consecutive timestamps allocated by PD, which are handed out in order.
+#[derive(Debug)]
+struct TsoBlock {
+    physical: i64,
+    next_logical: i64,
+    last_logical: i64,
+    /// When PD's response was received.
+    received: Instant,
+}
+
+impl TsoBlock {
+    /// The block of `count` timestamps PD responded with, which ends at `last`.
+    fn new(last: &pdpb::Timestamp, count: u32) -> TsoBlock {
+        TsoBlock {
+            physical: last.physical,
+            next_logical: last.logical - i64::from(count) + 1,
+            last_logical: last.logical,
+            received: Instant::now(),
+        }
+    }
+
+    /// Whether the block is too old to hand out what remains of it, see `TSO_PREFETCH_TTL`.
+    fn is_expired(&self) -> bool {
+        self.received.elapsed() > TSO_PREFETCH_TTL
+    }
+
+    /// The block in `prefetched` if it has timestamps left to hand out, dropping it otherwise.
+    fn live(prefetched: &mut Option<TsoBlock>) -> Option<&mut TsoBlock> {
+        if prefetched
+            .as_ref()
+            .map_or(false, |block| block.remaining() == 0 || block.is_expired())
+        {
+            *prefetched = None;
+        }
+        prefetched.as_mut()
+    }
+
+    fn remaining(&self) -> usize {
+        cmp::max(self.last_logical - self.next_logical + 1, 0) as usize
+    }
+
+    fn next(&mut self) -> Option<PdTimestamp> {
+        if self.next_logical > self.last_logical {
+            return None;
+        }
+        let ts = PdTimestamp {
+            physical: self.physical,
+            logical: self.next_logical,
+        };
+        self.next_logical += 1;
+        Some(ts)
+    }
+}
+
 enum PdTask {
     Init,
     Request,
     Response(Vec<oneshot::Sender<PdTimestamp>>, pdpb::TsoResponse),
+    /// Reconnect to the PD leader and re-establish the TSO stream after it failed.
+    Reconnect,
 }
 
 struct PdReactor {
@@ -59,51 +138,69 @@ struct PdReactor {
     tso_tx: Sender<pdpb::TsoRequest>,
     tso_rx: Option<Receiver<pdpb::TsoRequest>>,
 
-    handle: Option<JoinHandle<()>>,
+    runtime: Arc<dyn Runtime>,
+    tso_options: TsoOptions,
     tso_pending: Option<Vec<TsoChannel>>,
     tso_buffer: Option<Vec<TsoChannel>>,
     tso_batch: Vec<TsoChannel>,
+    /// Incremented whenever the TSO stream is replaced, so that the failures and responses of a
+    /// stale stream are told apart from those of the current one.
+    tso_generation: u64,
+    /// Whether the TSO stream failed and is being re-established.
+    tso_reconnecting: bool,
+    /// Whether a timer sends the batch once its wait window closes.
+    tso_wait_scheduled: bool,
+    /// Timestamps PD allocated ahead of time, which are handed out before asking PD for more.
+    tso_prefetched: Option<TsoBlock>,
 }
 
 impl Drop for PdReactor {
     fn drop(&mut self) {
-        if let Some(handle) = self.handle.take() {
-            handle.join().unwrap();
+        if let Some(task_tx) = self.task_tx.take() {
+            // Stop the dispatcher.
+            let _ = task_tx.unbounded_send(None);
         }
     }
 }
 
 impl PdReactor {
-    fn new() -> Self {
+    fn new(runtime: Arc<dyn Runtime>, tso_options: TsoOptions) -> Self {
         let (tso_tx, tso_rx) = channel(1);
         PdReactor {
             task_tx: None,
             tso_tx,
             tso_rx: Some(tso_rx),
-            handle: None,
+            runtime,
+            tso_options,
             tso_buffer: Some(Vec::with_capacity(8)),
             tso_batch: Vec::with_capacity(8),
             tso_pending: None,
+            tso_generation: 0,
+            tso_reconnecting: false,
+            tso_wait_scheduled: false,
+            tso_prefetched: None,
         }
     }
 
     fn start(&mut self, client: Arc<RwLock<LeaderClient>>) {
-        if self.handle.is_none() {
-            info!("starting pd reactor thread");
+        if self.task_tx.is_none() {
+            info!("starting pd dispatcher");
             let (task_tx, task_rx) = unbounded();
             task_tx.unbounded_send(Some(PdTask::Init)).unwrap();
             self.task_tx = Some(task_tx);
-            self.handle = Some(
-                thread::Builder::new()
-                    .name("dispatcher thread".to_owned())
-                    .spawn(move || Self::poll(&client, task_rx))
-                    .unwrap(),
-            )
+            self.runtime
+                .spawn(Self::poll(Arc::downgrade(&client), task_rx).boxed());
         } else {
             warn!("tso sender and receiver are stale, refreshing...");
             let (tso_tx, tso_rx) = channel(1);
             self.tso_tx = tso_tx;
             self.tso_rx = Some(tso_rx);
+            self.tso_generation += 1;
+            self.tso_reconnecting = false;
+            // The response to the pending request would arrive on the stale stream.
+            if self.tso_pending.take().is_some() {
+                self.tso_buffer = Some(Vec::with_capacity(8));
+            }
             self.schedule(PdTask::Init);
         }
     }
@@ -116,50 +213,92 @@ impl PdReactor {
             .expect("unbounded send should never fail");
     }
 
-    fn poll(client: &Arc<RwLock<LeaderClient>>, rx: UnboundedReceiver<Option<PdTask>>) {
-        let mut core = Core::new().unwrap();
-        let handle = core.handle();
-        {
-            let f = rx.take_while(|t| ready(t.is_some())).for_each(|t| {
-                Self::dispatch(&client, t.unwrap(), &handle);
-                ready(())
-            });
-            core.run(TryFutureExt::compat(f.unit_error())).unwrap();
-        }
+    /// Dispatch the tasks sent to `rx` until `None` is sent or the sender is dropped. Only a weak
+    /// reference to the client is held, so that it is dropped along with the reactor, which then
+    /// stops the dispatcher.
+    fn poll(
+        client: Weak<RwLock<LeaderClient>>,
+        rx: UnboundedReceiver<Option<PdTask>>,
+    ) -> impl Future<Output = ()> {
+        rx.take_while(|t| ready(t.is_some())).for_each(move |t| {
+            if let Some(client) = client.upgrade() {
+                Self::dispatch(&client, t.unwrap());
+            }
+            ready(())
+        })
     }
 
-    fn init(client: &Arc<RwLock<LeaderClient>>, handle: &OtherHandle) {
-        let client = Arc::clone(client);
-        let (tx, rx) = client.wl().client.tso().unwrap();
+    fn init(client: &Arc<RwLock<LeaderClient>>) {
+        let (runtime, generation) = {
+            let client = client.rl();
+            (
+                Arc::clone(&client.reactor.runtime),
+                client.reactor.tso_generation,
+            )
+        };
+        let tso = client.rl().client.tso();
+        let (tx, rx) = match tso {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("failed to open the tso stream: {:?}", e);
+                client.wl().reactor.tso_failed();
+                return;
+            }
+        };
         let tx = Compat01As03Sink::new(tx);
         let rx = Compat01As03::new(rx);
-        let tso_rx = client.wl().reactor.tso_rx.take().unwrap(); // Receiver<TsoRequest>: Stream
+        let tso_rx = {
+            let mut client = client.wl();
+            let reactor = &mut client.reactor;
+            if !reactor.tso_batch.is_empty() {
+                // Send the requests made while the stream was down.
+                reactor.schedule(PdTask::Request);
+            }
+            reactor.tso_rx.take().unwrap() // Receiver<TsoRequest>: Stream
+        };
 
-        handle.spawn(
+        // The streams end once the client, and with it the sender of the requests, is dropped.
+        let send_client = Arc::downgrade(client);
+        runtime.spawn(
             tx.sink_map_err(Into::into)
                 .send_all_compat(tso_rx.map(|r| (r, WriteFlags::default())))
-                .map(|r: Result<_>| match r {
+                .map(move |r: Result<_>| match r {
                     Ok((_sender, _)) => {
                         // FIXME(#54) the previous code doesn't work because we can't get mutable
                         // access to the underlying StreamingCallSink to call `cancel`. But I think
                         // that is OK because it will be canceled when it is dropped.
                         //
                         // _sender.get_mut().get_ref().cancel();
-                        Ok(())
                     }
                     Err(e) => {
                         error!("failed to send tso requests: {:?}", e);
-                        Err(())
+                        if let Some(client) = send_client.upgrade() {
+                            client.wl().reactor.tso_stream_failed(generation);
+                        }
                     }
                 })
-                .compat(),
+                .boxed(),
         );
 
-        handle.spawn(
+        let receive_client = Arc::downgrade(client);
+        runtime.spawn(
             rx.try_for_each(move |resp| {
+                let client = match receive_client.upgrade() {
+                    Some(client) => client,
+                    None => return ready(Ok(())),
+                };
                 let mut client = client.wl();
                 let reactor = &mut client.reactor;
-                let tso_pending = reactor.tso_pending.take().unwrap();
+                if reactor.tso_generation != generation {
+                    return ready(Ok(()));
+                }
+                let tso_pending = match reactor.tso_pending.take() {
+                    Some(tso_pending) => tso_pending,
+                    None => {
+                        warn!("unexpected tso response");
+                        return ready(Ok(()));
+                    }
+                };
                 reactor.schedule(PdTask::Response(tso_pending, resp));
                 if !reactor.tso_batch.is_empty() {
                     // Schedule another tso_batch of request
@@ -167,8 +306,21 @@ impl PdReactor {
                 }
                 ready(Ok(()))
             })
-            .map_err(|e| panic!("unexpected error: {:?}", e))
-            .compat(),
+            .map({
+                let client = Arc::downgrade(client);
+                move |r| {
+                    let client = match client.upgrade() {
+                        Some(client) => client,
+                        None => return,
+                    };
+                    match r {
+                        Ok(()) => warn!("tso stream closed by PD"),
+                        Err(e) => error!("failed to receive tso responses: {:?}", e),
+                    }
+                    client.wl().reactor.tso_stream_failed(generation);
+                }
+            })
+            .boxed(),
         );
     }
 
@@ -176,16 +328,36 @@ impl PdReactor {
         let mut client = client.wl();
         let cluster_id = client.cluster_id;
         let reactor = &mut client.reactor;
+        reactor.tso_wait_scheduled = false;
+        if reactor.tso_pending.is_some() || reactor.tso_reconnecting || reactor.tso_batch.is_empty()
+        {
+            // The requests are sent once the pending request is answered or the stream is back.
+            return;
+        }
+        if let Some(prefetched) = TsoBlock::live(&mut reactor.tso_prefetched) {
+            // Hand out the prefetched timestamps before asking PD for newer ones.
+            let count = cmp::min(prefetched.remaining(), reactor.tso_batch.len());
+            for request in reactor.tso_batch.drain(..count) {
+                let _ = request.send(prefetched.next().unwrap());
+            }
+            if reactor.tso_batch.is_empty() {
+                return;
+            }
+        }
+        let count = match reactor.tso_options.max_batch_size {
+            0 => reactor.tso_batch.len(),
+            max_batch_size => cmp::min(reactor.tso_batch.len(), max_batch_size),
+        };
         let mut tso_batch = reactor.tso_buffer.take().unwrap();
-        tso_batch.extend(reactor.tso_batch.drain(..));
+        tso_batch.extend(reactor.tso_batch.drain(..count));
         let mut request = pd_request!(cluster_id, pdpb::TsoRequest);
         let batch_size = observe_tso_batch(tso_batch.len());
-        request.set_count(batch_size);
+        request.set_count(batch_size + reactor.tso_options.prefetch as u32);
         reactor.tso_pending = Some(tso_batch);
-        reactor
-            .tso_tx
-            .try_send(request)
-            .expect("channel can never be full");
+        if let Err(e) = reactor.tso_tx.try_send(request) {
+            error!("failed to send tso request: {:?}", e);
+            reactor.tso_failed();
+        }
     }
 
     fn tso_response(
@@ -193,37 +365,146 @@ impl PdReactor {
         mut requests: Vec<TsoChannel>,
         response: &pdpb::TsoResponse,
     ) {
-        let timestamp = response.get_timestamp();
-        for (offset, request) in requests.drain(..).enumerate() {
-            request
-                .send(PdTimestamp {
-                    physical: timestamp.physical,
-                    logical: timestamp.logical + offset as i64,
-                })
-                .unwrap();
+        let mut block = TsoBlock::new(response.get_timestamp(), response.get_count());
+        for request in requests.drain(..) {
+            match block.next() {
+                // Fails if the caller is no longer waiting.
+                Some(ts) => {
+                    let _ = request.send(ts);
+                }
+                None => {
+                    error!("PD allocated fewer timestamps than requested");
+                    break;
+                }
+            }
+        }
+        let mut client = client.wl();
+        let reactor = &mut client.reactor;
+        reactor.tso_buffer = Some(requests);
+        if block.remaining() > 0 {
+            reactor.tso_prefetched = Some(block);
         }
-        client.wl().reactor.tso_buffer = Some(requests);
     }
 
-    fn dispatch(client: &Arc<RwLock<LeaderClient>>, task: PdTask, handle: &OtherHandle) {
+    fn dispatch(client: &Arc<RwLock<LeaderClient>>, task: PdTask) {
         match task {
             PdTask::Request => Self::tso_request(client),
             PdTask::Response(requests, response) => Self::tso_response(client, requests, &response),
-            PdTask::Init => Self::init(client, handle),
+            PdTask::Init => Self::init(client),
+            PdTask::Reconnect => Self::reconnect(client),
+        }
+    }
+
+    /// Handle the failure of the TSO stream of `generation`, unless it was replaced already.
+    fn tso_stream_failed(&mut self, generation: u64) {
+        if self.tso_generation == generation {
+            self.tso_failed();
+        }
+    }
+
+    /// Handle the failure of the current TSO stream: fail the request waiting for a response, as
+    /// it is unknown whether PD handled it, and re-establish the stream. Requests made in the
+    /// meantime are sent over the new stream.
+    fn tso_failed(&mut self) {
+        if self.tso_reconnecting {
+            return;
+        }
+        self.tso_reconnecting = true;
+        if self.tso_pending.take().is_some() {
+            self.tso_buffer = Some(Vec::with_capacity(8));
+        }
+        observe_tso_reconnect();
+        self.schedule(PdTask::Reconnect);
+    }
+
+    fn reconnect(client: &Arc<RwLock<LeaderClient>>) {
+        warn!("tso stream failed, reconnecting to the PD leader");
+        let runtime = Arc::clone(&client.rl().reactor.runtime);
+        let reconnect = LeaderClient::reconnect_async(client, RECONNECT_INTERVAL_SEC);
+        let client = Arc::downgrade(client);
+        runtime.spawn(
+            reconnect
+                .then({
+                    let runtime = Arc::clone(&runtime);
+                    move |result| {
+                        let client = match client.upgrade() {
+                            Some(client) => client,
+                            None => return Either::Left(ready(())),
+                        };
+                        {
+                            let mut leader = client.wl();
+                            match result {
+                                // The stream was restarted along with the connection.
+                                Ok(()) if !leader.reactor.tso_reconnecting => {
+                                    return Either::Left(ready(()));
+                                }
+                                // The connection was updated too recently to be updated again.
+                                Ok(()) => {}
+                                Err(e) => {
+                                    error!("failed to reconnect to the PD leader: {:?}", e);
+                                    // Nobody knows when PD will be back, so don't keep the callers
+                                    // waiting.
+                                    leader.reactor.tso_batch.clear();
+                                }
+                            }
+                        }
+                        let client = Arc::downgrade(&client);
+                        Either::Right(
+                            runtime
+                                .delay(Instant::now() + Duration::from_secs(RECONNECT_INTERVAL_SEC))
+                                .map(move |_| {
+                                    if let Some(client) = client.upgrade() {
+                                        client.rl().reactor.schedule(PdTask::Reconnect);
+                                    }
+                                }),
+                        )
+                    }
+                })
+                .boxed(),
+        );
+    }
+
+    /// Schedule sending the batch once its wait window closes, or right away if it is full.
+    fn schedule_tso_request(&mut self) {
+        let max_wait = self.tso_options.max_wait;
+        let max_batch_size = self.tso_options.max_batch_size;
+        let full = max_batch_size > 0 && self.tso_batch.len() >= max_batch_size;
+        if max_wait == Duration::from_secs(0) || full {
+            self.schedule(PdTask::Request);
+        } else if !self.tso_wait_scheduled {
+            self.tso_wait_scheduled = true;
+            let task_tx = self.task_tx.clone().unwrap();
+            self.runtime.spawn(
+                self.runtime
+                    .delay(Instant::now() + max_wait)
+                    .map(move |_| {
+                        // Fails if the dispatcher stopped.
+                        let _ = task_tx.unbounded_send(Some(PdTask::Request));
+                    })
+                    .boxed(),
+            );
         }
     }
 
     fn get_ts(&mut self) -> impl Future<Output = Result<PdTimestamp>> {
         let context = request_context("get_ts", ());
+        if self.tso_batch.is_empty() {
+            // Otherwise the caller would overtake those already waiting for a timestamp.
+            if let Some(ts) = TsoBlock::live(&mut self.tso_prefetched).and_then(TsoBlock::next) {
+                return Either::Left(ready(context.done(Ok(ts))));
+            }
+        }
         let (tx, rx) = oneshot::channel::<PdTimestamp>();
         self.tso_batch.push(tx);
-        if self.tso_pending.is_none() {
+        if self.tso_pending.is_none() && !self.tso_reconnecting {
             // Schedule tso request to run.
-            self.schedule(PdTask::Request);
+            self.schedule_tso_request();
         }
-        rx.map_err(Into::into)
-            .into_future()
-            .map(move |r| context.done(r))
+        Either::Right(
+            rx.map_err(Into::into)
+                .into_future()
+                .map(move |r| context.done(r)),
+        )
     }
 }
 
@@ -249,6 +530,8 @@ impl LeaderClient {
         endpoints: &[String],
         security_mgr: Arc<SecurityManager>,
         timeout: Duration,
+        runtime: Arc<dyn Runtime>,
+        tso_options: TsoOptions,
     ) -> Result<Arc<RwLock<LeaderClient>>> {
         let (client, members) = validate_endpoints(&env, endpoints, &security_mgr, timeout)?;
         let cluster_id = members.get_header().get_cluster_id();
@@ -258,12 +541,13 @@ impl LeaderClient {
             members,
             security_mgr,
             last_update: Instant::now(),
-            reactor: PdReactor::new(),
+            reactor: PdReactor::new(runtime, tso_options),
             cluster_id,
             timeout,
         }));
 
         client.wl().reactor.start(Arc::clone(&client));
+        Self::watch_members(&client);
         Ok(client)
     }
 
@@ -273,7 +557,7 @@ impl LeaderClient {
 
     // Re-establish connection with PD leader in synchronized fashion.
     pub fn reconnect(leader: &Arc<RwLock<LeaderClient>>, interval: u64) -> Result<()> {
-        warn!("updating pd client, blocking the tokio core");
+        warn!("updating PD client");
         let ((client, members), start) = {
             let leader = leader.rl();
             if leader.last_update.elapsed() < Duration::from_secs(interval) {
@@ -300,6 +584,126 @@ impl LeaderClient {
         warn!("updating PD client done, spent {:?}", start.elapsed());
         Ok(())
     }
+
+    /// [`reconnect`](LeaderClient::reconnect) on a thread of its own, as connecting to PD blocks
+    /// and must not hold up the other tasks on the runtime.
+    fn reconnect_async(
+        leader: &Arc<RwLock<LeaderClient>>,
+        interval: u64,
+    ) -> impl Future<Output = Result<()>> {
+        let (tx, rx) = oneshot::channel();
+        let leader = Arc::clone(leader);
+        let spawned = thread::Builder::new()
+            .name(thd_name!("pd-reconnect"))
+            .spawn(move || {
+                let _ = tx.send(LeaderClient::reconnect(&leader, interval));
+            });
+        match spawned {
+            Ok(_) => Either::Left(rx.map(|r| r.unwrap_or_else(|canceled| Err(canceled.into())))),
+            Err(e) => Either::Right(ready(Err(e.into()))),
+        }
+    }
+
+    /// Fetch the members of the PD cluster every `MEMBERS_UPDATE_INTERVAL` in the background, so
+    /// that PD nodes added to the cluster are known, and leader changes are followed before
+    /// requests fail because of them. Stops once the client is dropped, which the background tasks
+    /// only hold weak references to.
+    fn watch_members(leader: &Arc<RwLock<LeaderClient>>) {
+        let runtime = Arc::clone(&leader.rl().reactor.runtime);
+        let watch = loop_fn(Arc::downgrade(leader), {
+            let runtime = Arc::clone(&runtime);
+            move |leader: Weak<RwLock<LeaderClient>>| {
+                runtime
+                    .delay(Instant::now() + MEMBERS_UPDATE_INTERVAL)
+                    .then(move |_| {
+                        let leader = match leader.upgrade() {
+                            Some(leader) => leader,
+                            None => return Either::Left(ready(Ok(Loop::Break(())))),
+                        };
+                        let members = {
+                            let leader = leader.rl();
+                            let option = CallOption::default().timeout(leader.timeout);
+                            leader
+                                .client
+                                .get_members_async_opt(&pdpb::GetMembersRequest::default(), option)
+                        };
+                        let members = match members {
+                            Ok(members) => {
+                                Either::Left(cancel_on_drop(members).map_err(Error::from))
+                            }
+                            Err(e) => Either::Right(ready(Err(Error::from(e)))),
+                        };
+                        Either::Right(members.then(move |members| {
+                            Self::update_members(&leader, members).map(move |_| {
+                                Ok::<_, Error>(Loop::Continue(Arc::downgrade(&leader)))
+                            })
+                        }))
+                    })
+            }
+        });
+        runtime.spawn(watch.map(|_| ()).boxed());
+    }
+
+    /// Switch to the leader in `members` if it changed, or reconnect if the members could not be
+    /// fetched from the current leader.
+    fn update_members(
+        leader: &Arc<RwLock<LeaderClient>>,
+        members: Result<pdpb::GetMembersResponse>,
+    ) -> impl Future<Output = ()> {
+        match members {
+            Ok(members) => {
+                Self::follow_leader(leader, members);
+                Either::Left(ready(()))
+            }
+            Err(e) => {
+                warn!("failed to get the PD members, reconnecting: {:?}", e);
+                Either::Right(
+                    Self::reconnect_async(leader, RECONNECT_INTERVAL_SEC).map(|r| {
+                        if let Err(e) = r {
+                            error!("failed to reconnect to the PD leader: {:?}", e);
+                        }
+                    }),
+                )
+            }
+        }
+    }
+
+    /// Switch to the leader in `members` if it changed.
+    fn follow_leader(leader: &Arc<RwLock<LeaderClient>>, members: pdpb::GetMembersResponse) {
+        let mut client = leader.wl();
+        let cluster_id = members.get_header().get_cluster_id();
+        if cluster_id != client.cluster_id {
+            error!(
+                "PD leader moved to cluster {}, expected {}",
+                cluster_id, client.cluster_id
+            );
+            return;
+        }
+        if !members.has_leader() || members.get_leader() == client.members.get_leader() {
+            client.members = members;
+            return;
+        }
+
+        let new_leader = members.get_leader().clone();
+        info!("PD leader changed to {}", new_leader.get_name());
+        for ep in new_leader.get_client_urls() {
+            match client
+                .security_mgr
+                .connect(Arc::clone(&client.env), ep, pdpb::PdClient::new)
+            {
+                Ok(pd) => {
+                    client.client = pd;
+                    client.members = members;
+                    client.last_update = Instant::now();
+                    client.reactor.start(Arc::clone(leader));
+                    return;
+                }
+                Err(e) => error!("failed to connect to {}, {:?}", ep, e),
+            }
+        }
+        // Requests will reconnect once they fail.
+        client.members = members;
+    }
 }
 
 pub fn validate_endpoints(
@@ -434,3 +838,58 @@ pub fn try_connect_leader(
 
     Err(internal_err!("failed to connect to {:?}", members))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tso_block() {
+        let mut last = pdpb::Timestamp::default();
+        last.physical = 100;
+        last.logical = 12;
+        let mut block = TsoBlock::new(&last, 3);
+        assert_eq!(block.remaining(), 3);
+        for logical in 10..=12 {
+            assert_eq!(
+                block.next(),
+                Some(PdTimestamp {
+                    physical: 100,
+                    logical,
+                })
+            );
+        }
+        assert_eq!(block.remaining(), 0);
+        assert_eq!(block.next(), None);
+    }
+
+    #[test]
+    fn test_tso_block_prefetched() {
+        // PD returns the last timestamp of the block it allocated, so the batch gets the start of
+        // the block, and the prefetched timestamps end at the one PD returned.
+        let mut last = pdpb::Timestamp::default();
+        last.physical = 100;
+        last.logical = 20;
+        let mut block = TsoBlock::new(&last, 2 + 4);
+        let batch: Vec<i64> = (0..2).map(|_| block.next().unwrap().logical).collect();
+        assert_eq!(batch, vec![15, 16]);
+        assert_eq!(block.remaining(), 4);
+        let prefetched: Vec<i64> = (0..4).map(|_| block.next().unwrap().logical).collect();
+        assert_eq!(prefetched, vec![17, 18, 19, 20]);
+        assert_eq!(block.next(), None);
+    }
+
+    #[test]
+    fn test_tso_block_expired() {
+        let mut last = pdpb::Timestamp::default();
+        last.logical = 10;
+        let mut block = TsoBlock::new(&last, 4);
+        block.received -= TSO_PREFETCH_TTL * 2;
+        assert!(block.is_expired());
+        assert_eq!(block.remaining(), 4);
+
+        let mut prefetched = Some(block);
+        assert!(TsoBlock::live(&mut prefetched).is_none());
+        assert!(prefetched.is_none());
+    }
+}
@@ -6,7 +6,7 @@
 pub use kvproto::metapb::{Peer, Store};
 use kvproto::{kvrpcpb, metapb};
 
-pub use crate::rpc::pd::client::PdClient;
+pub use crate::rpc::pd::{client::PdClient, leader::TsoOptions};
 use crate::{Error, Key, Result};
 
 #[macro_use]
@@ -36,8 +36,24 @@ impl Region {
         Region { region, leader }
     }
 
-    pub fn switch_peer(&mut self, _to: StoreId) -> Result<()> {
-        unimplemented!()
+    /// Make the peer of the region on store `to` the leader, e.g. after TiKV reported it is the
+    /// new leader.
+    pub fn switch_peer(&mut self, to: StoreId) -> Result<()> {
+        let peer = self
+            .region
+            .get_peers()
+            .iter()
+            .find(|peer| peer.get_store_id() == to)
+            .cloned()
+            .ok_or_else(|| {
+                Error::stale_epoch(Some(format!(
+                    "region {} has no peer on store {}",
+                    self.region.get_id(),
+                    to
+                )))
+            })?;
+        self.leader = Some(peer);
+        Ok(())
     }
 
     pub fn contains(&self, key: &Key) -> bool {
@@ -50,7 +66,7 @@ impl Region {
     pub fn context(&self) -> Result<kvrpcpb::Context> {
         self.leader
             .as_ref()
-            .ok_or_else(|| Error::not_leader(self.region.get_id(), None))
+            .ok_or_else(|| Error::not_leader(self.region.get_id(), None, None))
             .map(|l| {
                 let mut ctx = kvrpcpb::Context::default();
                 ctx.set_region_id(self.region.get_id());
@@ -90,6 +106,16 @@ impl Region {
             .ok_or_else(|| Error::stale_epoch(None))
     }
 
+    /// The voting peers of the region other than the leader.
+    pub fn followers(&self) -> Vec<&Peer> {
+        let leader_id = self.leader.as_ref().map(Peer::get_id);
+        self.region
+            .get_peers()
+            .iter()
+            .filter(|peer| Some(peer.get_id()) != leader_id && !peer.get_is_learner())
+            .collect()
+    }
+
     pub fn meta(&self) -> metapb::Region {
         self.region.clone()
     }
@@ -6,15 +6,13 @@ use std::{
     time::{Duration, Instant},
 };
 
-use futures::compat::Compat01As03;
 use futures::future::{ok, ready, Either, Future, TryFutureExt};
 use futures::prelude::FutureExt;
 use log::*;
-use tokio_timer::timer::Handle;
 
 use crate::{
     compat::{loop_fn, Loop},
-    rpc::util::GLOBAL_TIMER_HANDLE,
+    rpc::Runtime,
     Result,
 };
 
@@ -26,7 +24,7 @@ pub struct Request<Resp, Func, Cli, Reconnect> {
     request_sent: usize,
 
     client: Arc<RwLock<Cli>>,
-    timer: Handle,
+    runtime: Arc<dyn Runtime>,
 
     resp: Option<Result<Resp>>,
     func: Func,
@@ -43,12 +41,18 @@ where
     Reconnect: FnMut(&Arc<RwLock<Cli>>, u64) -> Result<()> + Send + 'static,
     RespFuture: Future<Output = Result<Resp>> + Send + 'static,
 {
-    pub fn new(func: Func, client: Arc<RwLock<Cli>>, reconnect: Reconnect, retry: usize) -> Self {
+    pub fn new(
+        func: Func,
+        client: Arc<RwLock<Cli>>,
+        reconnect: Reconnect,
+        retry: usize,
+        runtime: Arc<dyn Runtime>,
+    ) -> Self {
         Request {
             reconnect_count: retry,
             request_sent: 0,
             client,
-            timer: GLOBAL_TIMER_HANDLE.clone(),
+            runtime,
             resp: None,
             func,
             reconnect,
@@ -74,11 +78,9 @@ where
                 Either::Left(ok(self))
             }
             Err(_) => Either::Right(
-                Compat01As03::new(
-                    self.timer
-                        .delay(Instant::now() + Duration::from_secs(RECONNECT_INTERVAL_SEC)),
-                )
-                .map(|_| Err(self)),
+                self.runtime
+                    .delay(Instant::now() + Duration::from_secs(RECONNECT_INTERVAL_SEC))
+                    .map(|_| Err(self)),
             ),
         }
     }
@@ -0,0 +1,187 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    cmp,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// The connections to the TiKV stores, by address.
+///
+/// Each store gets up to `size` connections, which requests are spread over round-robin. They are
+/// created lazily: as long as a store has fewer connections, [`get`](ConnectionPool::get) returns
+/// `None` and the caller is expected to [`add`](ConnectionPool::add) a new one.
+///
+/// Connections are kept until they are evicted, either because they broke, because their store was
+/// removed, or because the store was not used for `idle_timeout`.
+pub struct ConnectionPool<Conn> {
+    size: usize,
+    idle_timeout: Duration,
+    stores: HashMap<String, StoreConnections<Conn>>,
+}
+
+struct StoreConnections<Conn> {
+    connections: Vec<Arc<Conn>>,
+    next: AtomicUsize,
+    last_used: Mutex<Instant>,
+}
+
+impl<Conn> ConnectionPool<Conn> {
+    pub fn new(size: usize, idle_timeout: Duration) -> Self {
+        ConnectionPool {
+            size: cmp::max(size, 1),
+            idle_timeout,
+            stores: HashMap::new(),
+        }
+    }
+
+    /// The next connection to `address`, or `None` if a new connection should be added.
+    pub fn get(&self, address: &str) -> Option<Arc<Conn>> {
+        let store = self.stores.get(address)?;
+        if store.connections.len() < self.size {
+            return None;
+        }
+        *store.last_used.lock().unwrap() = Instant::now();
+        let index = store.next.fetch_add(1, Ordering::Relaxed) % store.connections.len();
+        Some(Arc::clone(&store.connections[index]))
+    }
+
+    /// Add a connection to `address`, unless it already has enough connections. Returns the
+    /// connection which should be used.
+    pub fn add(&mut self, address: &str, connection: Arc<Conn>) -> Arc<Conn> {
+        if let Some(connection) = self.get(address) {
+            return connection;
+        }
+        let store = self
+            .stores
+            .entry(address.to_owned())
+            .or_insert_with(|| StoreConnections {
+                connections: Vec::new(),
+                next: AtomicUsize::new(0),
+                last_used: Mutex::new(Instant::now()),
+            });
+        *store.last_used.lock().unwrap() = Instant::now();
+        store.connections.push(Arc::clone(&connection));
+        connection
+    }
+
+    /// The number of connections to `address`, which is the index of the next one.
+    pub fn len(&self, address: &str) -> usize {
+        self.stores
+            .get(address)
+            .map(|store| store.connections.len())
+            .unwrap_or(0)
+    }
+
+    /// Evict `connection`, e.g. because it broke. It is replaced by a new connection the next time
+    /// its store is used.
+    pub fn evict(&mut self, address: &str, connection: &Arc<Conn>) -> bool {
+        let store = match self.stores.get_mut(address) {
+            Some(store) => store,
+            None => return false,
+        };
+        let before = store.connections.len();
+        store.connections.retain(|c| !Arc::ptr_eq(c, connection));
+        let evicted = store.connections.len() < before;
+        if store.connections.is_empty() {
+            self.stores.remove(address);
+        }
+        evicted
+    }
+
+    /// Evict the connections to the stores whose address `keep` rejects, returning their
+    /// addresses.
+    pub fn retain_stores(&mut self, mut keep: impl FnMut(&str) -> bool) -> Vec<String> {
+        let evicted: Vec<String> = self
+            .stores
+            .keys()
+            .filter(|address| !keep(address))
+            .cloned()
+            .collect();
+        for address in &evicted {
+            self.stores.remove(address);
+        }
+        evicted
+    }
+
+    /// Evict the connections to the stores which were not used for `idle_timeout`, returning
+    /// their addresses.
+    pub fn evict_idle(&mut self) -> Vec<String> {
+        let idle_timeout = self.idle_timeout;
+        let idle: Vec<String> = self
+            .stores
+            .iter()
+            .filter(|(_, store)| store.last_used.lock().unwrap().elapsed() >= idle_timeout)
+            .map(|(address, _)| address.clone())
+            .collect();
+        for address in &idle {
+            self.stores.remove(address);
+        }
+        idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(pool: &mut ConnectionPool<usize>, address: &str) {
+        while pool.get(address).is_none() {
+            let index = pool.len(address);
+            pool.add(address, Arc::new(index));
+        }
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut pool = ConnectionPool::new(3, Duration::from_secs(60));
+        assert!(pool.get("a").is_none());
+        fill(&mut pool, "a");
+        assert_eq!(pool.len("a"), 3);
+
+        let picked: Vec<usize> = (0..6).map(|_| *pool.get("a").unwrap()).collect();
+        assert_eq!(picked, vec![0, 1, 2, 0, 1, 2]);
+
+        // A full store doesn't take more connections.
+        assert_eq!(*pool.add("a", Arc::new(42)), 0);
+        assert_eq!(pool.len("a"), 3);
+    }
+
+    #[test]
+    fn test_evict() {
+        let mut pool = ConnectionPool::new(2, Duration::from_secs(60));
+        fill(&mut pool, "a");
+        fill(&mut pool, "b");
+
+        let broken = pool.get("a").unwrap();
+        assert!(pool.evict("a", &broken));
+        assert!(!pool.evict("a", &broken));
+        assert!(pool.get("a").is_none());
+        assert_eq!(pool.len("a"), 1);
+
+        fill(&mut pool, "c");
+        assert_eq!(
+            pool.retain_stores(|address| address != "b"),
+            vec!["b".to_owned()]
+        );
+        assert_eq!(pool.len("b"), 0);
+        assert_eq!(pool.len("c"), 2);
+    }
+
+    #[test]
+    fn test_evict_idle() {
+        let mut pool = ConnectionPool::new(1, Duration::from_secs(0));
+        fill(&mut pool, "a");
+        assert_eq!(pool.evict_idle(), vec!["a".to_owned()]);
+        assert!(pool.get("a").is_none());
+
+        let mut pool = ConnectionPool::new(1, Duration::from_secs(3600));
+        fill(&mut pool, "a");
+        assert!(pool.evict_idle().is_empty());
+        assert!(pool.get("a").is_some());
+    }
+}
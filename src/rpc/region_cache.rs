@@ -0,0 +1,233 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    rpc::pd::{Peer, Region, RegionId, Store, StoreId},
+    Key,
+};
+
+/// A cache of the regions and stores of the cluster, so not every request needs to ask PD where
+/// its keys are.
+///
+/// Regions are kept ordered by their start keys, so the region containing a key is found with a
+/// single range lookup. Entries are never refreshed by the cache itself. Callers must invalidate
+/// regions whose requests fail because the cached information is outdated.
+#[derive(Default)]
+pub struct RegionCache {
+    regions: BTreeMap<Key, Region>,
+    start_keys: HashMap<RegionId, Key>,
+    /// The regions whose leader was switched to the one TiKV hinted at, rather than loaded from PD.
+    hinted_leaders: HashSet<RegionId>,
+    stores: HashMap<StoreId, Store>,
+}
+
+impl RegionCache {
+    /// The cached region containing `key`, if any.
+    pub fn region_by_key(&self, key: &Key) -> Option<Region> {
+        self.regions
+            .range(..=key.clone())
+            .next_back()
+            .map(|(_, region)| region)
+            .filter(|region| region.contains(key))
+            .cloned()
+    }
+
+    pub fn region_by_id(&self, id: RegionId) -> Option<Region> {
+        self.start_keys
+            .get(&id)
+            .and_then(|start_key| self.regions.get(start_key))
+            .cloned()
+    }
+
+    /// Cache `region`, replacing all cached regions it overlaps with.
+    pub fn add_region(&mut self, region: Region) {
+        let start_key = Key::from(region.start_key().to_vec());
+        let end_key = region.end_key();
+
+        let mut overlapping = Vec::new();
+        if let Some((key, previous)) = self.regions.range(..start_key.clone()).next_back() {
+            if previous.end_key().is_empty() || previous.end_key() > region.start_key() {
+                overlapping.push(key.clone());
+            }
+        }
+        overlapping.extend(
+            self.regions
+                .range(start_key.clone()..)
+                .take_while(|(key, _)| end_key.is_empty() || &key[..] < end_key)
+                .map(|(key, _)| key.clone()),
+        );
+        for key in overlapping {
+            if let Some(stale) = self.regions.remove(&key) {
+                self.start_keys.remove(&stale.id());
+                self.hinted_leaders.remove(&stale.id());
+            }
+        }
+        // The region may have moved, e.g. because it was merged with its left neighbour.
+        self.invalidate_region(region.id());
+
+        self.start_keys.insert(region.id(), start_key.clone());
+        self.regions.insert(start_key, region);
+    }
+
+    /// Switch the cached region `id` to `leader`. If the region is not cached or has no such peer,
+    /// it is invalidated so it will be reloaded from PD and `false` is returned.
+    pub fn switch_leader(&mut self, id: RegionId, leader: &Peer) -> bool {
+        let switched = self
+            .start_keys
+            .get(&id)
+            .and_then(|start_key| self.regions.get_mut(start_key))
+            .map(|region| region.switch_peer(leader.get_store_id()).is_ok())
+            .unwrap_or(false);
+        if switched {
+            self.hinted_leaders.insert(id);
+        } else {
+            self.invalidate_region(id);
+        }
+        switched
+    }
+
+    /// Whether the leader of the cached region `id` was switched to by
+    /// [`switch_leader`](RegionCache::switch_leader) since the region was loaded.
+    pub fn is_leader_hinted(&self, id: RegionId) -> bool {
+        self.hinted_leaders.contains(&id)
+    }
+
+    pub fn invalidate_region(&mut self, id: RegionId) {
+        self.hinted_leaders.remove(&id);
+        if let Some(start_key) = self.start_keys.remove(&id) {
+            self.regions.remove(&start_key);
+        }
+    }
+
+    pub fn store(&self, id: StoreId) -> Option<Store> {
+        self.stores.get(&id).cloned()
+    }
+
+    pub fn add_store(&mut self, store: Store) {
+        self.stores.insert(store.get_id(), store);
+    }
+
+    pub fn invalidate_store(&mut self, id: StoreId) {
+        self.stores.remove(&id);
+    }
+
+    /// Invalidate the cached stores whose ids `keep` rejects, returning their ids.
+    pub fn retain_stores(&mut self, mut keep: impl FnMut(StoreId) -> bool) -> Vec<StoreId> {
+        let invalidated: Vec<StoreId> = self
+            .stores
+            .keys()
+            .cloned()
+            .filter(|id| !keep(*id))
+            .collect();
+        for id in &invalidated {
+            self.stores.remove(id);
+        }
+        invalidated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kvproto::metapb;
+
+    fn region(id: RegionId, start_key: &str, end_key: &str) -> Region {
+        let mut region = metapb::Region::default();
+        region.set_id(id);
+        region.set_start_key(start_key.as_bytes().to_vec());
+        region.set_end_key(end_key.as_bytes().to_vec());
+        Region::new(region, None)
+    }
+
+    fn peer(id: u64, store_id: StoreId) -> Peer {
+        let mut peer = Peer::default();
+        peer.set_id(id);
+        peer.set_store_id(store_id);
+        peer
+    }
+
+    fn ids(cache: &RegionCache) -> Vec<RegionId> {
+        cache.regions.values().map(Region::id).collect()
+    }
+
+    #[test]
+    fn test_region_by_key() {
+        let mut cache = RegionCache::default();
+        cache.add_region(region(1, "", "b"));
+        cache.add_region(region(2, "d", "f"));
+        cache.add_region(region(3, "f", ""));
+
+        assert_eq!(cache.region_by_key(&"".into()).map(|r| r.id()), Some(1));
+        assert_eq!(cache.region_by_key(&"a".into()).map(|r| r.id()), Some(1));
+        assert!(cache.region_by_key(&"b".into()).is_none());
+        assert!(cache.region_by_key(&"c".into()).is_none());
+        assert_eq!(cache.region_by_key(&"d".into()).map(|r| r.id()), Some(2));
+        assert_eq!(cache.region_by_key(&"z".into()).map(|r| r.id()), Some(3));
+        assert_eq!(cache.region_by_id(2).map(|r| r.id()), Some(2));
+
+        cache.invalidate_region(2);
+        assert!(cache.region_by_key(&"d".into()).is_none());
+        assert!(cache.region_by_id(2).is_none());
+    }
+
+    #[test]
+    fn test_add_overlapping_region() {
+        let mut cache = RegionCache::default();
+        cache.add_region(region(1, "", "c"));
+        cache.add_region(region(2, "c", "e"));
+        cache.add_region(region(3, "e", "g"));
+        cache.add_region(region(4, "g", ""));
+
+        // A split region replaces the old one.
+        cache.add_region(region(5, "", "b"));
+        assert_eq!(ids(&cache), vec![5, 2, 3, 4]);
+
+        // A merged region replaces all regions it covers.
+        cache.add_region(region(2, "d", "h"));
+        assert_eq!(ids(&cache), vec![5, 2]);
+        assert!(cache.region_by_id(3).is_none());
+        assert!(cache.region_by_id(4).is_none());
+
+        cache.add_region(region(6, "a", ""));
+        assert_eq!(ids(&cache), vec![6]);
+    }
+
+    #[test]
+    fn test_switch_leader() {
+        let mut cache = RegionCache::default();
+        let mut replicated = region(1, "", "");
+        replicated
+            .region
+            .set_peers(vec![peer(1, 1), peer(2, 2), peer(3, 3)].into());
+        replicated.leader = Some(peer(1, 1));
+        cache.add_region(replicated);
+
+        assert!(!cache.is_leader_hinted(1));
+        assert!(cache.switch_leader(1, &peer(3, 3)));
+        let region = cache.region_by_id(1).unwrap();
+        assert_eq!(region.peer().unwrap(), peer(3, 3));
+        assert!(cache.is_leader_hinted(1));
+
+        // A leader on an unknown store means the cached region is outdated.
+        assert!(!cache.switch_leader(1, &peer(4, 4)));
+        assert!(cache.region_by_id(1).is_none());
+        assert!(!cache.is_leader_hinted(1));
+        assert!(!cache.switch_leader(2, &peer(1, 1)));
+    }
+
+    #[test]
+    fn test_retain_stores() {
+        let mut cache = RegionCache::default();
+        for id in 1..=3 {
+            let mut store = Store::default();
+            store.set_id(id);
+            cache.add_store(store);
+        }
+
+        assert_eq!(cache.retain_stores(|id| id != 2), vec![2]);
+        assert!(cache.store(1).is_some());
+        assert!(cache.store(2).is_none());
+        assert!(cache.store(3).is_some());
+    }
+}
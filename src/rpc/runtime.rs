@@ -0,0 +1,77 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{fmt, sync::Arc, time::Instant};
+
+use futures::compat::Compat01As03;
+use futures::executor::ThreadPool;
+use futures::future::BoxFuture;
+use futures::prelude::{FutureExt, TryFutureExt};
+use futures::task::SpawnExt;
+use log::*;
+
+use crate::{rpc::util::GLOBAL_TIMER_HANDLE, Result};
+
+/// Runs the background tasks of a client, such as the stream of timestamp requests to PD, and
+/// provides the timers its retries and deadlines wait on.
+///
+/// By default every client gets a [`DefaultRuntime`](DefaultRuntime) with a dedicated thread. To
+/// run the client on the runtime your application already uses instead, implement this trait for
+/// it and pass it to [`Config::runtime`](crate::Config::runtime).
+pub trait Runtime: Send + Sync {
+    /// Run `future` in the background until it completes. It must not be polled before `spawn`
+    /// returns, as the caller may hold locks the future needs.
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+
+    /// A future which resolves at `deadline`.
+    fn delay(&self, deadline: Instant) -> BoxFuture<'static, Result<()>>;
+}
+
+/// The [`Runtime`](Runtime) clients use unless they are configured with another one.
+///
+/// Background tasks run on a dedicated thread, and timers are driven by a timer thread shared by
+/// all clients.
+#[derive(Clone)]
+pub struct DefaultRuntime {
+    pool: ThreadPool,
+}
+
+impl DefaultRuntime {
+    /// Start the thread the background tasks run on.
+    pub fn new() -> Result<DefaultRuntime> {
+        let pool = ThreadPool::builder()
+            .pool_size(1)
+            .name_prefix(thd_name!("dispatcher"))
+            .create()?;
+        Ok(DefaultRuntime { pool })
+    }
+}
+
+impl Runtime for DefaultRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        if let Err(e) = self.pool.clone().spawn(future) {
+            error!("failed to spawn a background task: {:?}", e);
+        }
+    }
+
+    fn delay(&self, deadline: Instant) -> BoxFuture<'static, Result<()>> {
+        Compat01As03::new(GLOBAL_TIMER_HANDLE.delay(deadline))
+            .map_err(|e| internal_err!("timer failed: {}", e))
+            .boxed()
+    }
+}
+
+/// A [`Runtime`](Runtime) set in a [`Config`](crate::Config).
+#[derive(Clone)]
+pub(crate) struct RuntimeHandle(pub(crate) Arc<dyn Runtime>);
+
+impl fmt::Debug for RuntimeHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("RuntimeHandle")
+    }
+}
+
+impl PartialEq for RuntimeHandle {
+    fn eq(&self, other: &RuntimeHandle) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2018 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::{
+    ffi::CString,
     fs::File,
     io::Read,
     path::{Path, PathBuf},
@@ -15,6 +16,9 @@ use regex::Regex;
 
 use crate::Result;
 
+/// A channel argument which is only used to tell the channels to the same address apart.
+const CHANNEL_INDEX_ARG: &str = "tikv_client.channel_index";
+
 lazy_static! {
     static ref SCHEME_REG: Regex = Regex::new(r"^\s*(https?://)").unwrap();
 }
@@ -67,6 +71,34 @@ impl SecurityManager {
         addr: &str,
         factory: Factory,
     ) -> Result<Client>
+    where
+        Factory: FnOnce(Channel) -> Client,
+    {
+        self.connect_channel(env, addr, None, factory)
+    }
+
+    /// Like `connect`, but the channel gets its own connection instead of sharing one with the
+    /// other channels to `addr`, as long as their `index`es differ.
+    pub fn connect_distinct<Factory, Client>(
+        &self,
+        env: Arc<Environment>,
+        addr: &str,
+        index: usize,
+        factory: Factory,
+    ) -> Result<Client>
+    where
+        Factory: FnOnce(Channel) -> Client,
+    {
+        self.connect_channel(env, addr, Some(index), factory)
+    }
+
+    fn connect_channel<Factory, Client>(
+        &self,
+        env: Arc<Environment>,
+        addr: &str,
+        index: Option<usize>,
+        factory: Factory,
+    ) -> Result<Client>
     where
         Factory: FnOnce(Channel) -> Client,
     {
@@ -74,9 +106,13 @@ impl SecurityManager {
 
         let addr = SCHEME_REG.replace(addr, "");
 
-        let cb = ChannelBuilder::new(env)
+        let mut cb = ChannelBuilder::new(env)
             .keepalive_time(Duration::from_secs(10))
             .keepalive_timeout(Duration::from_secs(3));
+        if let Some(index) = index {
+            // gRPC shares connections between channels with the same arguments.
+            cb = cb.raw_cfg_int(CString::new(CHANNEL_INDEX_ARG).unwrap(), index as i32);
+        }
 
         let channel = if self.ca.is_empty() {
             cb.connect(&addr)
@@ -0,0 +1,385 @@
+// Copyright 2019 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::{
+    collections::HashMap,
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures::channel::{
+    mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    oneshot,
+};
+use futures::compat::{Compat01As03, Compat01As03Sink};
+use futures::future::{self, ready, Either, Future};
+use futures::prelude::{FutureExt, SinkExt, Stream, StreamExt, TryStreamExt};
+use futures::stream;
+use grpcio::{RpcStatus, RpcStatusCode, WriteFlags};
+use kvproto::{
+    kvrpcpb,
+    tikvpb::{
+        batch_commands_request, batch_commands_response, BatchCommandsRequest,
+        BatchCommandsResponse, TikvClient,
+    },
+};
+use log::*;
+
+use crate::{
+    compat::SinkCompat,
+    rpc::{backoff::sleep, Runtime},
+    Error, Result,
+};
+
+/// The maximum number of requests sent in a single BatchCommands message.
+const MAX_BATCH_SIZE: usize = 128;
+
+type Command = batch_commands_request::request::Cmd;
+type CommandResponse = batch_commands_response::response::Cmd;
+type Callback = oneshot::Sender<Result<CommandResponse>>;
+
+/// A request which can be sent over a BatchCommands stream.
+pub trait BatchCommand: Sized {
+    type Response;
+
+    fn into_command(self) -> Command;
+
+    fn from_command(response: CommandResponse) -> Option<Self::Response>;
+}
+
+macro_rules! batch_command {
+    ($request:ty, $response:ty, $variant:ident) => {
+        impl BatchCommand for $request {
+            type Response = $response;
+
+            fn into_command(self) -> Command {
+                Command::$variant(self)
+            }
+
+            fn from_command(response: CommandResponse) -> Option<$response> {
+                match response {
+                    CommandResponse::$variant(response) => Some(response),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+batch_command!(kvrpcpb::RawGetRequest, kvrpcpb::RawGetResponse, RawGet);
+batch_command!(
+    kvrpcpb::RawBatchGetRequest,
+    kvrpcpb::RawBatchGetResponse,
+    RawBatchGet
+);
+batch_command!(kvrpcpb::RawPutRequest, kvrpcpb::RawPutResponse, RawPut);
+batch_command!(
+    kvrpcpb::RawBatchPutRequest,
+    kvrpcpb::RawBatchPutResponse,
+    RawBatchPut
+);
+batch_command!(
+    kvrpcpb::RawDeleteRequest,
+    kvrpcpb::RawDeleteResponse,
+    RawDelete
+);
+batch_command!(
+    kvrpcpb::RawBatchDeleteRequest,
+    kvrpcpb::RawBatchDeleteResponse,
+    RawBatchDelete
+);
+batch_command!(kvrpcpb::RawScanRequest, kvrpcpb::RawScanResponse, RawScan);
+batch_command!(
+    kvrpcpb::RawBatchScanRequest,
+    kvrpcpb::RawBatchScanResponse,
+    RawBatchScan
+);
+batch_command!(
+    kvrpcpb::RawDeleteRangeRequest,
+    kvrpcpb::RawDeleteRangeResponse,
+    RawDeleteRange
+);
+
+/// The requests sent over a stream which are waiting for their responses.
+#[derive(Default)]
+struct Pending {
+    callbacks: HashMap<u64, Callback>,
+    closed: bool,
+    /// Whether TiKV rejected the stream as unimplemented, so it doesn't support BatchCommands.
+    unsupported: bool,
+}
+
+impl Pending {
+    /// Fail all waiting requests, and any request made from now on. The stream failed with
+    /// `error`, if it didn't simply end.
+    fn close(&mut self, error: Option<&Error>, reason: &str) {
+        if self.closed {
+            return;
+        }
+        self.closed = true;
+        self.unsupported = error.map_or(false, Error::is_grpc_unimplemented);
+        for (_, callback) in mem::replace(&mut self.callbacks, HashMap::new()) {
+            let _ = callback.send(Err(self.closed_error()));
+        }
+        warn!("BatchCommands stream closed: {}", reason);
+    }
+
+    /// The error the requests sent over the closed stream fail with. If TiKV doesn't support
+    /// BatchCommands, it is an `Unimplemented` status, which the requests are retried on as unary
+    /// requests.
+    fn closed_error(&self) -> Error {
+        if self.unsupported {
+            Error::from(grpcio::Error::RpcFailure(RpcStatus::new(
+                RpcStatusCode::Unimplemented,
+                Some("BatchCommands is not supported".to_owned()),
+            )))
+        } else {
+            stream_closed()
+        }
+    }
+}
+
+fn stream_closed() -> Error {
+    Error::from(grpcio::Error::RemoteStopped)
+}
+
+/// Multiplexes the requests to a TiKV store over a single BatchCommands stream.
+///
+/// Requests made while the previous batch is being sent, or within `max_wait` of the first request
+/// of a batch, are coalesced into a single message. Each request carries an id which TiKV echoes
+/// in its response, so responses can be matched with their requests no matter in which order and
+/// batches they arrive.
+///
+/// The stream is closed for good once it fails, failing all requests waiting for a response. The
+/// owner is expected to open a new one, or to fall back to unary requests if TiKV rejected the
+/// stream as unimplemented.
+pub struct BatchCommandsClient {
+    tx: UnboundedSender<(u64, Command, Callback)>,
+    pending: Arc<Mutex<Pending>>,
+    next_id: AtomicU64,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl BatchCommandsClient {
+    pub fn connect(
+        client: &TikvClient,
+        max_wait: Duration,
+        runtime: Arc<dyn Runtime>,
+    ) -> Result<BatchCommandsClient> {
+        let (sink, receiver) = client.batch_commands()?;
+        let (tx, rx) = unbounded();
+        let pending: Arc<Mutex<Pending>> = Default::default();
+
+        let requests = Self::batches(rx, Arc::clone(&pending), max_wait, Arc::clone(&runtime))
+            .map(|request| (request, WriteFlags::default()));
+        let send_pending = Arc::clone(&pending);
+        runtime.spawn(
+            Compat01As03Sink::new(sink)
+                .sink_map_err(Error::from)
+                .send_all_compat(Box::pin(requests))
+                .map(move |r| {
+                    let reason = match &r {
+                        Ok(_) => "client dropped".to_owned(),
+                        Err(e) => format!("failed to send requests: {}", e),
+                    };
+                    send_pending
+                        .lock()
+                        .unwrap()
+                        .close(r.err().as_ref(), &reason);
+                })
+                .boxed(),
+        );
+
+        let receive_pending = Arc::clone(&pending);
+        runtime.spawn(
+            Compat01As03::new(receiver)
+                .map_err(Error::from)
+                .try_for_each(move |response| {
+                    Self::on_response(&receive_pending, response);
+                    ready(Ok(()))
+                })
+                .map({
+                    let pending = Arc::clone(&pending);
+                    move |r| {
+                        let reason = match &r {
+                            Ok(_) => "TiKV closed the stream".to_owned(),
+                            Err(e) => format!("failed to receive responses: {}", e),
+                        };
+                        pending.lock().unwrap().close(r.err().as_ref(), &reason);
+                    }
+                })
+                .boxed(),
+        );
+
+        Ok(BatchCommandsClient {
+            tx,
+            pending,
+            next_id: AtomicU64::new(0),
+            runtime,
+        })
+    }
+
+    /// Whether the stream was closed, so requests can no longer be sent over it.
+    pub fn is_closed(&self) -> bool {
+        self.pending.lock().unwrap().closed
+    }
+
+    /// Whether TiKV rejected the stream as unimplemented, so the store does not support
+    /// BatchCommands.
+    pub fn is_unsupported(&self) -> bool {
+        self.pending.lock().unwrap().unsupported
+    }
+
+    /// Send `request` over the stream, failing with `DeadlineExceeded` if no response arrives
+    /// within `timeout`.
+    pub fn execute<Request: BatchCommand>(
+        &self,
+        request: Request,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Request::Response>> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (callback, response) = oneshot::channel();
+        if self
+            .tx
+            .unbounded_send((id, request.into_command(), callback))
+            .is_err()
+        {
+            return Either::Left(future::err(stream_closed()));
+        }
+        let pending = Arc::clone(&self.pending);
+        Either::Right(
+            future::select(response, Box::pin(sleep(&*self.runtime, timeout))).map(
+                move |r| match r {
+                    Either::Left((Ok(response), _)) => response.and_then(|response| {
+                        Request::from_command(response)
+                            .ok_or_else(|| internal_err!("mismatched BatchCommands response"))
+                    }),
+                    Either::Left((Err(canceled), _)) => Err(canceled.into()),
+                    Either::Right((_, response)) => {
+                        // Dropped first, so a request which is not sent yet is skipped rather
+                        // than added to the callbacks after they were cleaned up.
+                        drop(response);
+                        pending.lock().unwrap().callbacks.remove(&id);
+                        Err(Error::from(grpcio::Error::RpcFailure(RpcStatus::new(
+                            RpcStatusCode::DeadlineExceeded,
+                            Some(format!(
+                                "BatchCommands request timed out after {:?}",
+                                timeout
+                            )),
+                        ))))
+                    }
+                },
+            ),
+        )
+    }
+
+    /// The stream of messages sent to TiKV. Each message is made of a request, and all requests
+    /// made within `max_wait` of it (or while the previous message was sent).
+    fn batches(
+        rx: UnboundedReceiver<(u64, Command, Callback)>,
+        pending: Arc<Mutex<Pending>>,
+        max_wait: Duration,
+        runtime: Arc<dyn Runtime>,
+    ) -> impl Stream<Item = BatchCommandsRequest> {
+        stream::unfold(rx, move |rx| {
+            let pending = Arc::clone(&pending);
+            let runtime = Arc::clone(&runtime);
+            rx.into_future().then(move |(first, mut rx)| {
+                let first = match first {
+                    Some(first) => first,
+                    None => return Either::Left(ready(None)),
+                };
+                let wait = if max_wait > Duration::from_secs(0) {
+                    Either::Left(sleep(&*runtime, max_wait).map(|_| ()))
+                } else {
+                    Either::Right(ready(()))
+                };
+                Either::Right(wait.map(move |_| {
+                    let mut batch = BatchCommandsRequest::default();
+                    let mut pending = pending.lock().unwrap();
+                    let mut request = Some(first);
+                    while let Some((id, command, callback)) = request {
+                        if pending.closed {
+                            let _ = callback.send(Err(pending.closed_error()));
+                        } else if !callback.is_canceled() {
+                            let mut request = batch_commands_request::Request::default();
+                            request.cmd = Some(command);
+                            batch.requests.push(request);
+                            batch.request_ids.push(id);
+                            pending.callbacks.insert(id, callback);
+                        }
+                        request = if batch.requests.len() < MAX_BATCH_SIZE {
+                            rx.try_next().ok().and_then(|request| request)
+                        } else {
+                            None
+                        };
+                    }
+                    debug!(
+                        "send {} requests in a BatchCommands message",
+                        batch.requests.len()
+                    );
+                    Some((batch, rx))
+                }))
+            })
+        })
+        .filter(|batch| ready(!batch.requests.is_empty()))
+    }
+
+    fn on_response(pending: &Mutex<Pending>, response: BatchCommandsResponse) {
+        let mut pending = pending.lock().unwrap();
+        for (id, response) in response
+            .request_ids
+            .into_iter()
+            .zip(response.responses.into_iter())
+        {
+            match pending.callbacks.remove(&id) {
+                Some(callback) => {
+                    let response = response
+                        .cmd
+                        .ok_or_else(|| internal_err!("empty BatchCommands response"));
+                    let _ = callback.send(response);
+                }
+                None => debug!("BatchCommands response for unknown request {}", id),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc_failure(status: RpcStatusCode) -> Error {
+        Error::from(grpcio::Error::RpcFailure(RpcStatus::new(status, None)))
+    }
+
+    #[test]
+    fn test_close() {
+        let mut pending = Pending::default();
+        let (callback, mut response) = oneshot::channel();
+        pending.callbacks.insert(0, callback);
+        pending.close(
+            Some(&rpc_failure(RpcStatusCode::Unavailable)),
+            "unavailable",
+        );
+        assert!(pending.closed);
+        assert!(!pending.unsupported);
+        assert!(pending.callbacks.is_empty());
+        assert!(response.try_recv().unwrap().unwrap().is_err());
+
+        let mut pending = Pending::default();
+        pending.close(None, "closed");
+        assert!(!pending.unsupported);
+
+        let mut pending = Pending::default();
+        pending.close(
+            Some(&rpc_failure(RpcStatusCode::Unimplemented)),
+            "unimplemented",
+        );
+        assert!(pending.unsupported);
+        assert!(pending.closed_error().is_grpc_unimplemented());
+    }
+}
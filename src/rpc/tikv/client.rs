@@ -3,19 +3,29 @@
 // TODO: Remove this when txn is done.
 #![allow(dead_code)]
 
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    cmp, fmt,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
 
-use futures::compat::Compat01As03;
-use futures::future::Future;
+use futures::future::{Either, Future};
 use futures::prelude::{FutureExt, TryFutureExt};
 use grpcio::{CallOption, Environment};
 use kvproto::{errorpb, kvrpcpb, tikvpb::TikvClient};
+use log::*;
 
 use crate::{
+    raw::CommandPri,
     rpc::{
         client::{RawContext, TxnContext},
         security::SecurityManager,
-        tikv::context::{request_context, RequestContext},
+        tikv::{
+            batch::{BatchCommand, BatchCommandsClient},
+            context::{request_context, RequestContext},
+        },
+        util::{cancel_on_drop, HandyRwLock},
+        Runtime,
     },
     transaction::{Mutation, TxnInfo},
     Error, ErrorKind, Key, KvPair, Result, Value,
@@ -33,9 +43,14 @@ impl From<errorpb::Error> for Error {
     fn from(mut e: errorpb::Error) -> Error {
         let message = e.take_message();
         if e.has_not_leader() {
-            let e = e.get_not_leader();
+            let mut e = e.take_not_leader();
             let message = format!("{}. Leader: {:?}", message, e.get_leader());
-            Error::not_leader(e.get_region_id(), Some(message))
+            let leader = if e.has_leader() {
+                Some(e.take_leader())
+            } else {
+                None
+            };
+            Error::not_leader(e.get_region_id(), leader, Some(message))
         } else if e.has_region_not_found() {
             Error::region_not_found(e.get_region_not_found().get_region_id(), Some(message))
         } else if e.has_key_not_in_region() {
@@ -162,12 +177,15 @@ has_no_error!(kvrpcpb::RawBatchScanResponse);
 macro_rules! raw_request {
     ($context:expr, $type:ty) => {{
         let mut req = <$type>::default();
-        let (region, cf) = $context.into_inner();
-        req.set_context(region.into());
-        if let Some(cf) = cf {
+        let (region, options) = $context.into_inner();
+        let mut context: kvrpcpb::Context = region.into();
+        context.set_priority(options.priority.into());
+        context.set_not_fill_cache(options.not_fill_cache);
+        req.set_context(context);
+        if let Some(cf) = options.cf {
             req.set_cf(cf.to_string());
         }
-        req
+        (req, options.timeout, options.deadline)
     }};
 }
 
@@ -179,6 +197,16 @@ macro_rules! txn_request {
     }};
 }
 
+impl From<CommandPri> for kvrpcpb::CommandPri {
+    fn from(priority: CommandPri) -> kvrpcpb::CommandPri {
+        match priority {
+            CommandPri::Low => kvrpcpb::CommandPri::Low,
+            CommandPri::Normal => kvrpcpb::CommandPri::Normal,
+            CommandPri::High => kvrpcpb::CommandPri::High,
+        }
+    }
+}
+
 impl From<Mutation> for kvrpcpb::Mutation {
     fn from(mutation: Mutation) -> kvrpcpb::Mutation {
         let mut pb = kvrpcpb::Mutation::default();
@@ -218,23 +246,85 @@ pub struct KvClient {
     client: Arc<TikvClient>,
     timeout: Duration,
     address: String,
+    /// The stream raw requests are multiplexed over, if BatchCommands are enabled.
+    batch_commands: Option<RwLock<Arc<BatchCommandsClient>>>,
+    batch_commands_wait: Duration,
+    runtime: Arc<dyn Runtime>,
 }
 
 impl KvClient {
+    /// Connect to the TiKV store at `addr`, over a connection of its own among the connections
+    /// with other `index`es. If `batch_commands_wait` is given, raw requests are sent over a
+    /// BatchCommands stream, coalescing the requests made within that time.
     pub fn connect(
         env: Arc<Environment>,
         addr: &str,
+        index: usize,
         security_mgr: &Arc<SecurityManager>,
         timeout: Duration,
+        batch_commands_wait: Option<Duration>,
+        runtime: Arc<dyn Runtime>,
     ) -> Result<KvClient> {
-        let client = Arc::new(security_mgr.connect(env, addr, TikvClient::new)?);
+        let client = Arc::new(security_mgr.connect_distinct(env, addr, index, TikvClient::new)?);
+        let batch_commands = match batch_commands_wait {
+            Some(wait) => Some(RwLock::new(Arc::new(BatchCommandsClient::connect(
+                &client,
+                wait,
+                Arc::clone(&runtime),
+            )?))),
+            None => None,
+        };
         Ok(KvClient {
             client,
             timeout,
             address: addr.to_owned(),
+            batch_commands,
+            batch_commands_wait: batch_commands_wait.unwrap_or_default(),
+            runtime,
         })
     }
 
+    /// The BatchCommands stream to send raw requests over, or `None` if they should be sent as
+    /// unary requests. A closed stream is replaced by a new one, unless TiKV rejected it as
+    /// unimplemented. The requests which were waiting on a rejected stream fail with that status,
+    /// which they are retried on, and then take the unary path.
+    fn batch_commands(&self) -> Option<Arc<BatchCommandsClient>> {
+        let lock = self.batch_commands.as_ref()?;
+        let current = Arc::clone(&lock.rl());
+        if !current.is_closed() {
+            return Some(current);
+        }
+        if current.is_unsupported() {
+            return None;
+        }
+
+        let mut current = lock.wl();
+        if current.is_closed() {
+            match BatchCommandsClient::connect(
+                &self.client,
+                self.batch_commands_wait,
+                Arc::clone(&self.runtime),
+            ) {
+                Ok(client) => {
+                    info!("reopened BatchCommands stream to {}", self.address);
+                    *current = Arc::new(client);
+                }
+                Err(e) => {
+                    warn!(
+                        "failed to reopen BatchCommands stream to {}, sending unary requests: {}",
+                        self.address, e
+                    );
+                    return None;
+                }
+            }
+        }
+        Some(Arc::clone(&current))
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
     pub fn kv_get(
         &self,
         context: TxnContext,
@@ -247,9 +337,7 @@ impl KvClient {
 
         self.execute(request_context(
             "kv_get",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_get_async_opt(&req, opt).map(Compat01As03::new)
-            },
+            move |cli: Arc<TikvClient>, opt: _| cli.kv_get_async_opt(&req, opt).map(cancel_on_drop),
         ))
     }
 
@@ -272,7 +360,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_scan",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_scan_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_scan_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -296,7 +384,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_prewrite",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_prewrite_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_prewrite_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -316,7 +404,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_commit",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_commit_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_commit_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -333,7 +421,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_import",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_import_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_import_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -351,7 +439,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_cleanup",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_cleanup_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_cleanup_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -369,7 +457,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_batch_get",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_batch_get_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_batch_get_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -388,7 +476,7 @@ impl KvClient {
             "kv_batch_rollback",
             move |cli: Arc<TikvClient>, opt: _| {
                 cli.kv_batch_rollback_async_opt(&req, opt)
-                    .map(Compat01As03::new)
+                    .map(cancel_on_drop)
             },
         ))
     }
@@ -408,7 +496,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_scan_lock",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_scan_lock_async_opt(&req, opt).map(Compat01As03::new)
+                cli.kv_scan_lock_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -428,8 +516,7 @@ impl KvClient {
         self.execute(request_context(
             "kv_resolve_lock",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_resolve_lock_async_opt(&req, opt)
-                    .map(Compat01As03::new)
+                cli.kv_resolve_lock_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
@@ -444,9 +531,7 @@ impl KvClient {
 
         self.execute(request_context(
             "kv_gc",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_gc_async_opt(&req, opt).map(Compat01As03::new)
-            },
+            move |cli: Arc<TikvClient>, opt: _| cli.kv_gc_async_opt(&req, opt).map(cancel_on_drop),
         ))
     }
 
@@ -463,41 +548,47 @@ impl KvClient {
         self.execute(request_context(
             "kv_delete_range",
             move |cli: Arc<TikvClient>, opt: _| {
-                cli.kv_delete_range_async_opt(&req, opt)
-                    .map(Compat01As03::new)
+                cli.kv_delete_range_async_opt(&req, opt).map(cancel_on_drop)
             },
         ))
     }
 
     pub fn raw_get(&self, context: RawContext, key: Key) -> impl Future<Output = Result<Value>> {
-        let mut req = raw_request!(context, kvrpcpb::RawGetRequest);
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawGetRequest);
         req.set_key(key.into_inner());
 
-        self.execute(request_context(
-            "raw_get",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_get_async_opt(&req, opt).map(Compat01As03::new)
-            },
-        ))
+        self.execute_raw(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_get", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_get_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
         .map_ok(|mut resp| resp.take_value().into())
     }
 
+    /// Get the values of `keys`. If the request fails, the keys are handed back with the error.
     pub fn raw_batch_get(
         &self,
         context: RawContext,
-        keys: impl Iterator<Item = Key>,
-    ) -> impl Future<Output = Result<Vec<KvPair>>> {
-        let mut req = raw_request!(context, kvrpcpb::RawBatchGetRequest);
-        req.set_keys(keys.map(|x| x.into_inner()).collect());
+        keys: Vec<Key>,
+    ) -> impl Future<Output = std::result::Result<Vec<KvPair>, (Vec<Key>, Error)>> {
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawBatchGetRequest);
+        req.set_keys(keys.into_iter().map(|x| x.into_inner()).collect());
 
-        self.execute(request_context(
-            "raw_batch_get",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_batch_get_async_opt(&req, opt)
-                    .map(Compat01As03::new)
-            },
-        ))
-        .map_ok(|mut resp| Self::convert_from_grpc_pairs(resp.take_pairs()))
+        self.execute_raw_returning(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_batch_get", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_batch_get_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
+        .map(|r| match r {
+            Ok(mut resp) => Ok(Self::convert_from_grpc_pairs(resp.take_pairs())),
+            Err((mut req, e)) => Err((req.take_keys().into_iter().map(Key::from).collect(), e)),
+        })
     }
 
     pub fn raw_put(
@@ -506,66 +597,83 @@ impl KvClient {
         key: Key,
         value: Value,
     ) -> impl Future<Output = Result<()>> {
-        let mut req = raw_request!(context, kvrpcpb::RawPutRequest);
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawPutRequest);
         req.set_key(key.into_inner());
         req.set_value(value.into_inner());
 
-        self.execute(request_context(
-            "raw_put",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_put_async_opt(&req, opt).map(Compat01As03::new)
-            },
-        ))
+        self.execute_raw(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_put", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_put_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
         .map_ok(|_| ())
     }
 
+    /// Put `pairs`. If the request fails, the pairs are handed back with the error.
     pub fn raw_batch_put(
         &self,
         context: RawContext,
         pairs: Vec<KvPair>,
-    ) -> impl Future<Output = Result<()>> {
-        let mut req = raw_request!(context, kvrpcpb::RawBatchPutRequest);
+    ) -> impl Future<Output = std::result::Result<(), (Vec<KvPair>, Error)>> {
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawBatchPutRequest);
         req.set_pairs(Self::convert_to_grpc_pairs(pairs));
 
-        self.execute(request_context(
-            "raw_batch_put",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_batch_put_async_opt(&req, opt)
-                    .map(Compat01As03::new)
-            },
-        ))
-        .map_ok(|_| ())
+        self.execute_raw_returning(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_batch_put", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_batch_put_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
+        .map(|r| match r {
+            Ok(_) => Ok(()),
+            Err((mut req, e)) => Err((Self::convert_from_grpc_pairs(req.take_pairs()), e)),
+        })
     }
 
     pub fn raw_delete(&self, context: RawContext, key: Key) -> impl Future<Output = Result<()>> {
-        let mut req = raw_request!(context, kvrpcpb::RawDeleteRequest);
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawDeleteRequest);
         req.set_key(key.into_inner());
 
-        self.execute(request_context(
-            "raw_delete",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_delete_async_opt(&req, opt).map(Compat01As03::new)
-            },
-        ))
+        self.execute_raw(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_delete", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_delete_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
         .map_ok(|_| ())
     }
 
+    /// Delete `keys`. If the request fails, the keys are handed back with the error.
     pub fn raw_batch_delete(
         &self,
         context: RawContext,
         keys: Vec<Key>,
-    ) -> impl Future<Output = Result<()>> {
-        let mut req = raw_request!(context, kvrpcpb::RawBatchDeleteRequest);
+    ) -> impl Future<Output = std::result::Result<(), (Vec<Key>, Error)>> {
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawBatchDeleteRequest);
         req.set_keys(keys.into_iter().map(|x| x.into_inner()).collect());
 
-        self.execute(request_context(
-            "raw_batch_delete",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_batch_delete_async_opt(&req, opt)
-                    .map(Compat01As03::new)
-            },
-        ))
-        .map_ok(|_| ())
+        self.execute_raw_returning(
+            timeout,
+            deadline,
+            req,
+            request_context(
+                "raw_batch_delete",
+                |cli: Arc<TikvClient>, req: &_, opt: _| {
+                    cli.raw_batch_delete_async_opt(req, opt).map(cancel_on_drop)
+                },
+            ),
+        )
+        .map(|r| match r {
+            Ok(_) => Ok(()),
+            Err((mut req, e)) => Err((req.take_keys().into_iter().map(Key::from).collect(), e)),
+        })
     }
 
     pub fn raw_scan(
@@ -576,20 +684,24 @@ impl KvClient {
         limit: u32,
         key_only: bool,
     ) -> impl Future<Output = Result<Vec<KvPair>>> {
-        let mut req = raw_request!(context, kvrpcpb::RawScanRequest);
-        start_key
-            .map(|k| req.set_start_key(k.into_inner()))
-            .unwrap();
-        end_key.map(|k| req.set_end_key(k.into_inner())).unwrap();
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawScanRequest);
+        if let Some(start_key) = start_key {
+            req.set_start_key(start_key.into_inner());
+        }
+        if let Some(end_key) = end_key {
+            req.set_end_key(end_key.into_inner());
+        }
         req.set_limit(limit);
         req.set_key_only(key_only);
 
-        self.execute(request_context(
-            "raw_scan",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_scan_async_opt(&req, opt).map(Compat01As03::new)
-            },
-        ))
+        self.execute_raw(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_scan", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_scan_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
         .map_ok(|mut resp| Self::convert_from_grpc_pairs(resp.take_kvs()))
     }
 
@@ -600,18 +712,19 @@ impl KvClient {
         each_limit: u32,
         key_only: bool,
     ) -> impl Future<Output = Result<Vec<KvPair>>> {
-        let mut req = raw_request!(context, kvrpcpb::RawBatchScanRequest);
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawBatchScanRequest);
         req.set_ranges(Self::convert_to_grpc_ranges(ranges));
         req.set_each_limit(each_limit);
         req.set_key_only(key_only);
 
-        self.execute(request_context(
-            "raw_batch_scan",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_batch_scan_async_opt(&req, opt)
-                    .map(Compat01As03::new)
-            },
-        ))
+        self.execute_raw(
+            timeout,
+            deadline,
+            req,
+            request_context("raw_batch_scan", |cli: Arc<TikvClient>, req: &_, opt: _| {
+                cli.raw_batch_scan_async_opt(req, opt).map(cancel_on_drop)
+            }),
+        )
         .map_ok(|mut resp| Self::convert_from_grpc_pairs(resp.take_kvs()))
     }
 
@@ -621,22 +734,40 @@ impl KvClient {
         start_key: Key,
         end_key: Key,
     ) -> impl Future<Output = Result<()>> {
-        let mut req = raw_request!(context, kvrpcpb::RawDeleteRangeRequest);
+        let (mut req, timeout, deadline) = raw_request!(context, kvrpcpb::RawDeleteRangeRequest);
         req.set_start_key(start_key.into_inner());
         req.set_end_key(end_key.into_inner());
 
-        self.execute(request_context(
-            "raw_delete_range",
-            move |cli: Arc<TikvClient>, opt: _| {
-                cli.raw_delete_range_async_opt(&req, opt)
-                    .map(Compat01As03::new)
-            },
-        ))
+        self.execute_raw(
+            timeout,
+            deadline,
+            req,
+            request_context(
+                "raw_delete_range",
+                |cli: Arc<TikvClient>, req: &_, opt: _| {
+                    cli.raw_delete_range_async_opt(req, opt).map(cancel_on_drop)
+                },
+            ),
+        )
         .map_ok(|_| ())
     }
 
     fn execute<Executor, Resp, RpcFuture>(
         &self,
+        context: RequestContext<Executor>,
+    ) -> impl Future<Output = Result<Resp>>
+    where
+        Executor: FnOnce(Arc<TikvClient>, CallOption) -> ::grpcio::Result<RpcFuture>,
+        RpcFuture: Future<Output = std::result::Result<Resp, ::grpcio::Error>>,
+        Resp: HasRegionError + HasError + Sized + Clone,
+    {
+        self.execute_with_timeout(None, context)
+    }
+
+    /// Like `execute`, but `timeout` (if given) overrides the timeout of the client.
+    fn execute_with_timeout<Executor, Resp, RpcFuture>(
+        &self,
+        timeout: Option<Duration>,
         mut context: RequestContext<Executor>,
     ) -> impl Future<Output = Result<Resp>>
     where
@@ -647,24 +778,123 @@ impl KvClient {
         let executor = context.executor();
         executor(
             Arc::clone(&self.client),
-            CallOption::default().timeout(self.timeout),
+            CallOption::default().timeout(timeout.unwrap_or(self.timeout)),
         )
         .unwrap()
         .map(|r| match r {
             Err(e) => Err(ErrorKind::Grpc(e))?,
-            Ok(mut r) => {
-                if let Some(e) = r.region_error() {
-                    Err(e)
-                } else if let Some(e) = r.error() {
-                    Err(e)
-                } else {
-                    Ok(r)
-                }
-            }
+            Ok(r) => Self::check_response(r),
         })
         .map(move |r| context.done(r))
     }
 
+    /// Send a raw request, over the BatchCommands stream if there is one.
+    fn execute_raw<Req, Executor, RpcFuture>(
+        &self,
+        timeout: Option<Duration>,
+        deadline: Option<Instant>,
+        req: Req,
+        mut context: RequestContext<Executor>,
+    ) -> impl Future<Output = Result<Req::Response>>
+    where
+        Req: BatchCommand,
+        Executor: FnOnce(Arc<TikvClient>, &Req, CallOption) -> ::grpcio::Result<RpcFuture>,
+        RpcFuture: Future<Output = std::result::Result<Req::Response, ::grpcio::Error>>,
+        Req::Response: HasRegionError + HasError + Sized + Clone,
+    {
+        let timeout = self.raw_timeout(timeout, deadline);
+        let response = match self.batch_commands() {
+            Some(batch_commands) => Either::Left(batch_commands.execute(req, timeout)),
+            None => {
+                let executor = context.executor();
+                Either::Right(
+                    executor(
+                        Arc::clone(&self.client),
+                        &req,
+                        CallOption::default().timeout(timeout),
+                    )
+                    .unwrap()
+                    .map_err(|e| Error::from(ErrorKind::Grpc(e))),
+                )
+            }
+        };
+        response
+            .map(|r| r.and_then(Self::check_response))
+            .map(move |r| context.done(r))
+    }
+
+    /// Like `execute_raw`, but hands `req` back along with the error if the request fails, so
+    /// that it can be resent without keeping a copy of what it was made from.
+    fn execute_raw_returning<Req, Executor, RpcFuture>(
+        &self,
+        timeout: Option<Duration>,
+        deadline: Option<Instant>,
+        req: Req,
+        mut context: RequestContext<Executor>,
+    ) -> impl Future<Output = std::result::Result<Req::Response, (Req, Error)>>
+    where
+        Req: BatchCommand + Clone,
+        Executor: FnOnce(Arc<TikvClient>, &Req, CallOption) -> ::grpcio::Result<RpcFuture>,
+        RpcFuture: Future<Output = std::result::Result<Req::Response, ::grpcio::Error>>,
+        Req::Response: HasRegionError + HasError + Sized + Clone,
+    {
+        let timeout = self.raw_timeout(timeout, deadline);
+        let response = match self.batch_commands() {
+            // The stream consumes the request, so it has to be copied to be handed back.
+            Some(batch_commands) => Either::Left(batch_commands.execute(req.clone(), timeout)),
+            None => {
+                let executor = context.executor();
+                Either::Right(
+                    executor(
+                        Arc::clone(&self.client),
+                        &req,
+                        CallOption::default().timeout(timeout),
+                    )
+                    .unwrap()
+                    .map_err(|e| Error::from(ErrorKind::Grpc(e))),
+                )
+            }
+        };
+        response.map(move |r| {
+            context
+                .done(r.and_then(Self::check_response))
+                .map_err(|e| (req, e))
+        })
+    }
+
+    /// The timeout of a raw RPC: `timeout`, or the timeout of the client if there is none, cut
+    /// short so that the RPC can't outlive the `deadline` of the request it is made for.
+    fn raw_timeout(&self, timeout: Option<Duration>, deadline: Option<Instant>) -> Duration {
+        let timeout = timeout.unwrap_or(self.timeout);
+        match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                cmp::min(
+                    timeout,
+                    if deadline > now {
+                        deadline - now
+                    } else {
+                        Duration::from_secs(0)
+                    },
+                )
+            }
+            None => timeout,
+        }
+    }
+
+    fn check_response<Resp>(mut resp: Resp) -> Result<Resp>
+    where
+        Resp: HasRegionError + HasError,
+    {
+        if let Some(e) = resp.region_error() {
+            Err(e)
+        } else if let Some(e) = resp.error() {
+            Err(e)
+        } else {
+            Ok(resp)
+        }
+    }
+
     #[inline]
     fn convert_to_grpc_pair(pair: KvPair) -> kvrpcpb::KvPair {
         let mut result = kvrpcpb::KvPair::default();
@@ -696,8 +926,12 @@ impl KvClient {
     fn convert_to_grpc_range(range: (Option<Key>, Option<Key>)) -> kvrpcpb::KeyRange {
         let (start, end) = range;
         let mut range = kvrpcpb::KeyRange::default();
-        start.map(|k| range.set_start_key(k.into_inner())).unwrap();
-        end.map(|k| range.set_end_key(k.into_inner())).unwrap();
+        if let Some(start) = start {
+            range.set_start_key(start.into_inner());
+        }
+        if let Some(end) = end {
+            range.set_end_key(end.into_inner());
+        }
         range
     }
 
@@ -1,5 +1,6 @@
 // Copyright 2018 TiKV Project Authors. Licensed under Apache-2.0.
 
+pub mod batch;
 pub mod client;
 pub mod context;
 
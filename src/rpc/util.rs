@@ -6,9 +6,16 @@ use std::{
     time::Duration,
 };
 
+use futures::compat::Compat01As03;
+use futures::future::{self, Either, Future};
+use futures::prelude::FutureExt;
+use futures01::{Async, Future as Future01, Poll as Poll01};
+use grpcio::ClientUnaryReceiver;
 use lazy_static::*;
 use tokio_timer::{self, timer::Handle};
 
+use crate::{Error, Result};
+
 macro_rules! internal_err {
     ($e:expr) => ({
         let kind = $crate::Error::internal_error(
@@ -81,6 +88,59 @@ fn start_global_timer() -> Handle {
     rx.recv().unwrap()
 }
 
+/// A unary gRPC call which is canceled if it is dropped before it completes.
+///
+/// Dropping a `ClientUnaryReceiver` does not cancel its call, so the call would keep running on
+/// the server although nobody is waiting for its result anymore.
+pub struct CancelOnDrop<T> {
+    receiver: ClientUnaryReceiver<T>,
+    done: bool,
+}
+
+impl<T> Future01 for CancelOnDrop<T> {
+    type Item = T;
+    type Error = grpcio::Error;
+
+    fn poll(&mut self) -> Poll01<T, grpcio::Error> {
+        let result = self.receiver.poll();
+        self.done = match result {
+            Ok(Async::NotReady) => false,
+            _ => true,
+        };
+        result
+    }
+}
+
+impl<T> Drop for CancelOnDrop<T> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.receiver.cancel();
+        }
+    }
+}
+
+/// Turn a unary gRPC call into a future which cancels the call when it is dropped.
+pub fn cancel_on_drop<T>(receiver: ClientUnaryReceiver<T>) -> Compat01As03<CancelOnDrop<T>> {
+    Compat01As03::new(CancelOnDrop {
+        receiver,
+        done: false,
+    })
+}
+
+/// Resolve to the result of `future`, or fail with
+/// [`ErrorKind::DeadlineExceeded`](crate::ErrorKind::DeadlineExceeded) if `timer` resolves first.
+/// `future` is dropped then, which cancels the RPCs it is waiting for.
+pub fn with_deadline<T>(
+    future: impl Future<Output = Result<T>>,
+    timer: impl Future<Output = Result<()>> + Unpin,
+) -> impl Future<Output = Result<T>> {
+    future::select(Box::pin(future), timer).map(|r| match r {
+        Either::Left((result, _)) => result,
+        Either::Right((Ok(()), _)) => Err(Error::deadline_exceeded()),
+        Either::Right((Err(e), _)) => Err(e),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -133,4 +193,28 @@ mod tests {
         futures::executor::block_on(Compat01As03::new(delay)).unwrap();
         assert!(timer.elapsed() >= ::std::time::Duration::from_millis(100));
     }
+
+    #[test]
+    fn test_with_deadline() {
+        use crate::rpc::{DefaultRuntime, Runtime};
+
+        let runtime = DefaultRuntime::new().unwrap();
+        let result = futures::executor::block_on(with_deadline(
+            futures::future::pending::<Result<()>>(),
+            runtime.delay(::std::time::Instant::now() + Duration::from_millis(10)),
+        ));
+        match result {
+            Err(e) => match e.kind() {
+                crate::ErrorKind::DeadlineExceeded => {}
+                _ => panic!("unexpected error: {}", e),
+            },
+            Ok(_) => panic!("the deadline was not enforced"),
+        }
+
+        let result = futures::executor::block_on(with_deadline(
+            futures::future::ok(42),
+            runtime.delay(::std::time::Instant::now() + Duration::from_secs(60)),
+        ));
+        assert_eq!(result.unwrap(), 42);
+    }
 }
@@ -2,7 +2,19 @@
 
 const NUM_TEST_KEYS: u32 = 100;
 use crate::integration_tests::pd_addr;
-use tikv_client::{raw::Client, Config, Key, KvPair, Value};
+use futures::future::{join_all, BoxFuture};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tikv_client::{
+    raw::{Client, CommandPri},
+    Config, DefaultRuntime, ErrorKind, Key, KvPair, ReplicaReadMode, Result, Runtime, StoreState,
+    Value,
+};
 
 fn generate_key(id: i32) -> Key {
     format!("testkey_{}", id).into_bytes().into()
@@ -78,12 +90,29 @@ async fn test_existence<'a>(
 
     assert_eq!(
         client
-            .batch_get(all_keys)
+            .batch_get(all_keys.clone())
             .await
             .expect("Could not get value in batch"),
         existing_pairs,
     );
 
+    let mut all_values: Vec<Option<Value>> = existing_pairs
+        .iter()
+        .map(|pair| Some(pair.value().clone()))
+        .collect();
+    all_values.extend(not_existing_keys.iter().map(|_| None));
+    let mut keys_with_duplicates = all_keys.clone();
+    keys_with_duplicates.extend(all_keys.into_iter().rev());
+    let mut values_with_duplicates = all_values.clone();
+    values_with_duplicates.extend(all_values.into_iter().rev());
+    assert_eq!(
+        client
+            .batch_get_ordered(keys_with_duplicates)
+            .await
+            .expect("Could not get ordered values in batch"),
+        values_with_duplicates,
+    );
+
     assert_eq!(
         client
             .batch_get(not_existing_keys)
@@ -100,6 +129,14 @@ async fn test_existence<'a>(
         existing_pairs,
     );
 
+    assert_eq!(
+        client
+            .scan_prefix("testkey_", NUM_TEST_KEYS)
+            .await
+            .expect("Could not scan prefix"),
+        existing_pairs,
+    );
+
     assert_eq!(
         client
             .scan(test_key_start.clone()..test_key_end.clone(), NUM_TEST_KEYS)
@@ -110,10 +147,249 @@ async fn test_existence<'a>(
     );
 }
 
+async fn test_prefix(client: &Client) {
+    let tenant_a = client.with_prefix("testkey_1/");
+    let tenant_b = client.with_prefix("testkey_2/");
+
+    assert!(tenant_a.put("key", "a").await.is_ok());
+    assert!(tenant_b.put("key", "b").await.is_ok());
+    assert_eq!(
+        tenant_a.get("key").await.expect("Could not get value"),
+        Some(Value::from("a")),
+    );
+    assert_eq!(
+        client
+            .get("testkey_2/key")
+            .await
+            .expect("Could not get value"),
+        Some(Value::from("b")),
+    );
+    assert_eq!(
+        tenant_a
+            .scan(.., NUM_TEST_KEYS)
+            .await
+            .expect("Could not scan"),
+        vec![KvPair::new("key", "a")],
+    );
+
+    assert!(tenant_a.delete_range(..).await.is_ok());
+    assert!(tenant_a
+        .scan(.., NUM_TEST_KEYS)
+        .await
+        .expect("Could not scan")
+        .is_empty());
+    assert_eq!(
+        tenant_b.get("key").await.expect("Could not get value"),
+        Some(Value::from("b")),
+    );
+    assert!(tenant_b.delete("key").await.is_ok());
+}
+
+async fn test_batch_partial(client: &Client) {
+    let pairs: Vec<KvPair> = (20..30)
+        .map(|i| KvPair::new(generate_key(i), generate_value(i)))
+        .collect();
+    let result = client
+        .batch_put_partial(pairs.clone())
+        .await
+        .expect("Could not batch put");
+    assert!(result.is_complete());
+    assert_eq!(result.succeeded().len(), pairs.len());
+
+    let keys: Vec<Key> = pairs.iter().map(|pair| pair.key().clone()).collect();
+    let result = client
+        .batch_get_partial(keys.clone())
+        .await
+        .expect("Could not batch get");
+    assert!(result.is_complete());
+    let mut found = result.into_result().unwrap();
+    found.sort_by(|a, b| a.key().cmp(b.key()));
+    assert_eq!(found, pairs);
+
+    let result = client
+        .batch_delete_partial(keys)
+        .await
+        .expect("Could not batch delete");
+    assert!(result.is_complete());
+    assert_eq!(result.failed_keys().count(), 0);
+}
+
+async fn test_request_options(client: &Client) {
+    assert!(client
+        .put(generate_key(0), generate_value(0))
+        .timeout(Duration::from_secs(5))
+        .priority(CommandPri::High)
+        .await
+        .is_ok());
+    assert_eq!(
+        client
+            .get(generate_key(0))
+            .priority(CommandPri::Low)
+            .replica_read(ReplicaReadMode::Follower)
+            .not_fill_cache()
+            .await
+            .expect("Could not get value"),
+        Some(generate_value(0)),
+    );
+    assert_eq!(
+        client
+            .scan(generate_key(0)..=generate_key(0), 10)
+            .not_fill_cache()
+            .replica_read(ReplicaReadMode::LeaderAndFollower)
+            .await
+            .expect("Could not scan"),
+        vec![KvPair::new(generate_key(0), generate_value(0))],
+    );
+}
+
+async fn test_prefetch_regions(client: &Client) {
+    let regions = client
+        .prefetch_regions(generate_key(0)..generate_key(NUM_TEST_KEYS as i32 - 1))
+        .deadline(Instant::now() + Duration::from_secs(10))
+        .await
+        .expect("Could not prefetch regions");
+    assert!(regions >= 1);
+    assert_eq!(
+        client
+            .prefetch_regions(generate_key(1)..generate_key(1))
+            .await
+            .expect("Could not prefetch regions"),
+        0,
+    );
+}
+
+async fn test_cluster_info(client: &Client) {
+    let cluster = client.cluster_info();
+    let stores = cluster.stores().await.expect("Could not get stores");
+    assert!(stores
+        .iter()
+        .any(|store| store.state() == StoreState::Up && !store.address().is_empty()));
+
+    let region = cluster
+        .region(generate_key(0))
+        .await
+        .expect("Could not get region");
+    assert!(region.start_key() <= &generate_key(0));
+    assert!(region.end_key().is_empty() || region.end_key() > &generate_key(0));
+    let leader = region.leader().expect("Region has no leader");
+    assert!(stores.iter().any(|store| store.id() == leader.store_id()));
+
+    let regions = cluster.regions(..).await.expect("Could not get regions");
+    assert!(regions.contains(&region));
+    assert!(regions[0].start_key().is_empty());
+    assert!(regions.last().unwrap().end_key().is_empty());
+}
+
+async fn test_split_range(client: &Client) {
+    let start_key = generate_key(0);
+    let end_key = generate_key(NUM_TEST_KEYS as i32 - 1);
+    let chunks = client
+        .split_range(start_key.clone()..end_key.clone(), 4)
+        .await
+        .expect("Could not split range");
+    assert!(!chunks.is_empty() && chunks.len() <= 4);
+    assert_eq!(chunks[0].0, start_key);
+    assert_eq!(chunks.last().unwrap().1, Some(end_key));
+    for pair in chunks.windows(2) {
+        assert_eq!(pair[0].1.as_ref(), Some(&pair[1].0));
+    }
+
+    let chunks = client
+        .split_range(Key::default().., 1)
+        .await
+        .expect("Could not split range");
+    assert_eq!(chunks, vec![(Key::default(), None)]);
+}
+
+async fn test_split_and_scatter(client: &Client) {
+    let split_key = generate_key(NUM_TEST_KEYS as i32 / 2);
+    client
+        .split_regions(vec![split_key.clone()])
+        .await
+        .expect("Could not split regions");
+    let region = client
+        .cluster_info()
+        .region(split_key.clone())
+        .await
+        .expect("Could not get region");
+    assert_eq!(region.start_key(), &split_key);
+    // The keys looked up in a prefixed view are prefixed as well.
+    let prefixed = client
+        .with_prefix(split_key.clone())
+        .cluster_info()
+        .region(Key::default())
+        .await
+        .expect("Could not get region");
+    assert_eq!(prefixed.id(), region.id());
+
+    client
+        .scatter_region(region.id())
+        .await
+        .expect("Could not scatter region");
+    client
+        .wait_for_scatter(region.id())
+        .deadline(Instant::now() + Duration::from_secs(60))
+        .await
+        .expect("Could not wait for scatter");
+}
+
+async fn test_gc_safe_point(client: &Client) {
+    let safe_point = client
+        .get_gc_safe_point()
+        .await
+        .expect("Could not get GC safe point");
+    assert_eq!(
+        client
+            .update_gc_safe_point(safe_point)
+            .await
+            .expect("Could not update GC safe point"),
+        safe_point
+    );
+
+    let service_id = "tikv-client-test";
+    let min_safe_point = client
+        .update_service_gc_safe_point(service_id, Duration::from_secs(60), safe_point)
+        .await
+        .expect("Could not update service GC safe point");
+    assert!(min_safe_point <= safe_point);
+    client
+        .remove_service_gc_safe_point(service_id)
+        .await
+        .expect("Could not remove service GC safe point");
+}
+
+#[runtime::test(runtime_tokio::Tokio)]
+async fn deadline_raw_test() {
+    let client = connect().await;
+
+    client
+        .put(generate_key(0), generate_value(0))
+        .deadline(Instant::now() + Duration::from_secs(10))
+        .await
+        .expect("Could not put kv pair");
+
+    let expired = client
+        .get(generate_key(0))
+        .deadline(Instant::now())
+        .await
+        .expect_err("A request past its deadline succeeded");
+    match expired.kind() {
+        ErrorKind::DeadlineExceeded => {}
+        _ => panic!("unexpected error: {}", expired),
+    }
+
+    wipe_all(&client).await;
+}
+
 #[runtime::test(runtime_tokio::Tokio)]
 async fn basic_raw_test() {
     let client = connect().await;
 
+    test_prefetch_regions(&client).await;
+    test_cluster_info(&client).await;
+    test_split_range(&client).await;
+    test_split_and_scatter(&client).await;
+    test_gc_safe_point(&client).await;
     test_empty(&client).await;
 
     assert!(client.put(generate_key(0), generate_value(0)).await.is_ok());
@@ -151,6 +427,10 @@ async fn basic_raw_test() {
     )
     .await;
 
+    test_prefix(&client).await;
+    test_request_options(&client).await;
+    test_batch_partial(&client).await;
+
     wipe_all(&client).await;
     test_existence(
         &client,
@@ -159,3 +439,91 @@ async fn basic_raw_test() {
     )
     .await;
 }
+
+#[runtime::test(runtime_tokio::Tokio)]
+async fn batch_commands_raw_test() {
+    let client = Client::new(Config::new(pd_addr()).batch_commands(Duration::from_millis(1)))
+        .await
+        .expect("Could not connect to tikv");
+
+    let pairs: Vec<KvPair> = (30..40)
+        .map(|i| KvPair::new(generate_key(i), generate_value(i)))
+        .collect();
+    let puts = pairs.iter().cloned().map(|pair| {
+        let (key, value) = pair.into_inner();
+        client.put(key, value)
+    });
+    for result in join_all(puts).await {
+        assert!(result.is_ok());
+    }
+
+    let gets = pairs.iter().map(|pair| client.get(pair.key().clone()));
+    let values: Vec<Option<Value>> = join_all(gets)
+        .await
+        .into_iter()
+        .map(|value| value.expect("Could not get value"))
+        .collect();
+    assert_eq!(
+        values,
+        pairs
+            .iter()
+            .map(|pair| Some(pair.value().clone()))
+            .collect::<Vec<_>>(),
+    );
+
+    let keys: Vec<Key> = pairs.into_iter().map(KvPair::into_key).collect();
+    assert!(client.batch_delete(keys.clone()).await.is_ok());
+    assert!(client
+        .batch_get(keys)
+        .await
+        .expect("Could not get values in batch")
+        .is_empty());
+}
+
+/// A runtime which counts the tasks and timers of the client.
+struct CountingRuntime {
+    inner: DefaultRuntime,
+    spawned: AtomicUsize,
+    delays: AtomicUsize,
+}
+
+impl Runtime for CountingRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        self.spawned.fetch_add(1, Ordering::SeqCst);
+        self.inner.spawn(future)
+    }
+
+    fn delay(&self, deadline: Instant) -> BoxFuture<'static, Result<()>> {
+        self.delays.fetch_add(1, Ordering::SeqCst);
+        self.inner.delay(deadline)
+    }
+}
+
+#[runtime::test(runtime_tokio::Tokio)]
+async fn custom_runtime_raw_test() {
+    let runtime = Arc::new(CountingRuntime {
+        inner: DefaultRuntime::new().unwrap(),
+        spawned: AtomicUsize::new(0),
+        delays: AtomicUsize::new(0),
+    });
+    let client = Client::new(Config::new(pd_addr()).runtime(runtime.clone()))
+        .await
+        .expect("Could not connect to tikv");
+
+    assert!(client
+        .put(generate_key(50), generate_value(50))
+        .await
+        .is_ok());
+    assert_eq!(
+        client
+            .get(generate_key(50))
+            .await
+            .expect("Could not get value"),
+        Some(generate_value(50)),
+    );
+    assert!(client.delete(generate_key(50)).await.is_ok());
+
+    // The PD dispatcher runs on the runtime, and every request is timed by it.
+    assert!(runtime.spawned.load(Ordering::SeqCst) >= 1);
+    assert!(runtime.delays.load(Ordering::SeqCst) >= 3);
+}